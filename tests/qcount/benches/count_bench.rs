@@ -0,0 +1,44 @@
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use qcount::counter::{CountOptions, count_content};
+use qcount::fastcount::count_bytes;
+use qcount::language::Language;
+use std::hint::black_box;
+use std::path::Path;
+
+/// Builds a synthetic source file of roughly `target_bytes` mixing code,
+/// comment, blank and long lines.
+fn synthetic_corpus(target_bytes: usize) -> String {
+    let chunk = "\
+// line comment describing the function below
+fn compute(value: u64) -> u64 {
+    let doubled = value * 2; // trailing comment
+
+    /* block comment
+       spanning lines */
+    let message = \"日本語のコメントと文字列も含める\";
+    doubled + message.len() as u64 + 0x5f3759df + 0xdeadbeef + 0xcafebabe + 0x8badf00d + 0xfeedface
+}
+
+";
+    chunk.repeat(target_bytes / chunk.len() + 1)
+}
+
+fn bench_count(c: &mut Criterion) {
+    let corpus = synthetic_corpus(32 << 20);
+    let options = CountOptions::default();
+    let path = Path::new("synthetic.rs");
+
+    let mut group = c.benchmark_group("count");
+    group.throughput(Throughput::Bytes(corpus.len() as u64));
+    group.sample_size(20);
+    group.bench_function("state_machine", |b| {
+        b.iter(|| count_content(black_box(&corpus), path, Language::Rust, &options))
+    });
+    group.bench_function("fast_path", |b| {
+        b.iter(|| count_bytes(black_box(corpus.as_bytes()), path, Language::Rust, &options))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_count);
+criterion_main!(benches);
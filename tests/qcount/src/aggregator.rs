@@ -1,125 +1,470 @@
-use crate::counter::FileStats;
+use crate::baseline::BaselineCheck;
+use crate::churn::Churn;
+use crate::codeowners::CodeOwners;
+use crate::counter::{FileStats, TextStats};
+use crate::estimate::Estimate;
+use crate::hygiene::Hygiene;
 use crate::language::Language;
+use crate::marker::{MarkerCounts, MarkerHit, merge_counts};
+use crate::package::{Package, PackageIndex};
+use crate::symbol::Symbols;
+use schemars::JsonSchema;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize)]
+/// Version of the JSON report layout. Bump on breaking changes to `Report`.
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Totals of the files in one language.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct LangSummary {
     pub language: Language,
-    pub files: u64,
-    pub total_lines: u64,
-    pub code_lines: u64,
-    pub blank_lines: u64,
-    pub comment_lines: u64,
+    #[serde(flatten)]
+    pub totals: Totals,
 }
 
 impl LangSummary {
     fn new(language: Language) -> Self {
         LangSummary {
             language,
-            files: 0,
-            total_lines: 0,
-            code_lines: 0,
-            blank_lines: 0,
-            comment_lines: 0,
+            totals: Totals::default(),
         }
     }
+}
+
+/// Counts summed over a group of files: every file, or those of one
+/// language, directory, root, package, owner or license.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Totals {
+    pub files: u64,
+    pub total_lines: u64,
+    pub code_lines: u64,
+    pub blank_lines: u64,
+    pub comment_lines: u64,
+    /// Documentation lines (markdown cells of notebooks)
+    pub doc_lines: u64,
+    pub test_code_lines: u64,
+    pub production_code_lines: u64,
+    pub text: TextStats,
+    /// Marker tags found in comments, filled with `--markers`
+    pub markers: MarkerCounts,
+    /// Line-ending and whitespace audit, filled with `--hygiene`
+    pub hygiene: Hygiene,
+    /// Notebook cells (code and markdown)
+    pub cells: u64,
+    /// Functions, types and modules declared, filled with `--symbols`
+    pub symbols: Symbols,
+}
 
-    fn add(&mut self, fs: &FileStats) {
+impl Totals {
+    pub(crate) fn add(&mut self, fs: &FileStats) {
         self.files += 1;
         self.total_lines += fs.total_lines;
         self.code_lines += fs.code_lines;
         self.blank_lines += fs.blank_lines;
         self.comment_lines += fs.comment_lines;
+        self.doc_lines += fs.doc_lines;
+        self.test_code_lines += fs.test_code_lines;
+        self.production_code_lines += fs.production_code_lines;
+        self.text.merge(&fs.text);
+        merge_counts(&mut self.markers, &fs.markers);
+        self.hygiene.merge(&fs.hygiene);
+        self.cells += fs.cells;
+        self.symbols.merge(&fs.symbols);
+    }
+
+    pub(crate) fn merge(&mut self, other: &Totals) {
+        self.files += other.files;
+        self.total_lines += other.total_lines;
+        self.code_lines += other.code_lines;
+        self.blank_lines += other.blank_lines;
+        self.comment_lines += other.comment_lines;
+        self.doc_lines += other.doc_lines;
+        self.test_code_lines += other.test_code_lines;
+        self.production_code_lines += other.production_code_lines;
+        self.text.merge(&other.text);
+        merge_counts(&mut self.markers, &other.markers);
+        self.hygiene.merge(&other.hygiene);
+        self.cells += other.cells;
+        self.symbols.merge(&other.symbols);
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct DirSummary {
     pub path: PathBuf,
     pub by_language: HashMap<String, LangSummary>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Summary of the files attributed to one root path.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RootSummary {
+    pub root: PathBuf,
+    pub total: Totals,
+    pub by_language: Vec<LangSummary>,
+}
+
+/// Summary of the files attributed to one package (nearest manifest).
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PackageSummary {
+    /// None for files outside every discovered package
+    pub package: Option<Package>,
+    pub total: Totals,
+    pub by_language: Vec<LangSummary>,
+}
+
+/// Summary of the files owned by one CODEOWNERS owner (team or user).
+/// A file with several owners counts toward each of them.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct OwnerSummary {
+    pub owner: String,
+    pub total: Totals,
+    pub by_language: Vec<LangSummary>,
+}
+
+/// Summary of the files whose header carries one license.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct LicenseSummary {
+    /// SPDX expression, or the SPDX identifier of a recognized license notice
+    /// (`LicenseRef-unknown-GPL-family` for a GNU notice without a version)
+    pub license: String,
+    pub total: Totals,
+    pub by_language: Vec<LangSummary>,
+}
+
+/// Run metadata recorded alongside the counts.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Meta {
+    /// qcount version that produced the report
+    pub tool_version: String,
+    /// Root paths that were walked, in command-line order
+    pub roots: Vec<PathBuf>,
+    /// Start of the run (RFC 3339, UTC)
+    pub timestamp: String,
+    /// Wall-clock time of walk + count + aggregate in milliseconds
+    pub elapsed_ms: u64,
+    /// Worker threads used for counting
+    pub threads: usize,
+    /// `.qcount.toml` the settings were read from, if any
+    pub config: Option<PathBuf>,
+    /// `--include` patterns in effect
+    pub includes: Vec<String>,
+    /// `--exclude` patterns in effect
+    pub excludes: Vec<String>,
+    /// Marker tags searched in comments (`--markers`)
+    pub markers: Vec<String>,
+    /// `--hygiene`: the `hygiene` sections were filled
+    pub hygiene: bool,
+    /// `--symbols`: the `symbols` sections were filled
+    pub symbols: bool,
+    /// Width above which lines are counted in `text.long_lines`
+    pub long_line_width: usize,
+    /// `--total-only`: comments were not classified, code is every non-blank line
+    pub total_only: bool,
+    /// `--strict-ascii-blank`: the BOM and zero-width characters were not treated as blank
+    pub strict_ascii_blank: bool,
+    /// Files of a known language that could not be read (e.g. not UTF-8)
+    pub skipped_files: u64,
+}
+
+/// Report layout published as `schema/qcount-report.v1.schema.json`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[schemars(title = "qcount report")]
 pub struct Report {
-    pub total: LangSummary,
+    #[schemars(extend("const" = REPORT_SCHEMA_VERSION))]
+    pub schema_version: u32,
+    pub meta: Meta,
+    pub total: Totals,
     pub by_language: Vec<LangSummary>,
     pub by_directory: Vec<DirSummary>,
+    /// Per-file statistics sorted by path, filled with `--by-file`
+    pub by_file: Vec<FileStats>,
+    /// Marker occurrences sorted by path and line, filled with `--list-markers`
+    pub marker_hits: Vec<MarkerHit>,
+    /// Per-root breakdown, filled only when several roots are given
+    pub by_root: Vec<RootSummary>,
+    /// Per-package breakdown, filled with `--by-package`
+    pub by_package: Vec<PackageSummary>,
+    /// Per-owner breakdown, filled with `--by-owner`
+    pub by_owner: Vec<OwnerSummary>,
+    /// Files no CODEOWNERS rule assigns an owner to, filled with `--by-owner`
+    pub unowned_files: Vec<PathBuf>,
+    /// Per-license breakdown, filled with `--licenses`
+    pub by_license: Vec<LicenseSummary>,
+    /// Files without a recognized license header, filled with `--licenses`
+    pub unlicensed_files: Vec<PathBuf>,
+    /// Line churn against a git revision, present when `--since` is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub churn: Option<Churn>,
+    /// Copies skipped by `--dedupe` and the lines they would have added,
+    /// present when `--dedupe` is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicates: Option<Totals>,
+    /// Ratchet rules checked against `--baseline`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineCheck>,
+    /// COCOMO estimate, present when `--estimate` is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Estimate>,
 }
 
-pub fn aggregate(stats: Vec<FileStats>, by_dir: bool) -> Report {
-    // Aggregate by language
-    let mut lang_map: HashMap<String, LangSummary> = HashMap::new();
-    // Aggregate by directory
-    let mut dir_map: HashMap<PathBuf, HashMap<String, LangSummary>> = HashMap::new();
-
-    let mut total = LangSummary::new(Language::Rust); // placeholder language for total
-    total.language = Language::Rust; // will be replaced below with a special "total" approach
-
-    // Build a global total
-    let mut global = LangSummary {
-        language: Language::Rust,
-        files: 0,
-        total_lines: 0,
-        code_lines: 0,
-        blank_lines: 0,
-        comment_lines: 0,
-    };
-
-    for fs in &stats {
-        let lang_name = format!("{:?}", fs.language);
-
-        // Language aggregation
-        let entry = lang_map
-            .entry(lang_name.clone())
-            .or_insert_with(|| LangSummary::new(fs.language.clone()));
-        entry.add(fs);
-
-        // Global total
-        global.files += 1;
-        global.total_lines += fs.total_lines;
-        global.code_lines += fs.code_lines;
-        global.blank_lines += fs.blank_lines;
-        global.comment_lines += fs.comment_lines;
-
-        // Directory aggregation
-        if by_dir {
-            if let Some(parent) = fs.path.parent() {
-                let dir_entry = dir_map
-                    .entry(parent.to_path_buf())
-                    .or_insert_with(HashMap::new);
-                let lang_in_dir = dir_entry
-                    .entry(lang_name.clone())
-                    .or_insert_with(|| LangSummary::new(fs.language.clone()));
-                lang_in_dir.add(fs);
+/// Language summaries keyed by `format!("{:?}", language)`.
+pub(crate) type LangMap = HashMap<String, LangSummary>;
+
+pub(crate) fn add_to_langs(langs: &mut LangMap, fs: &FileStats) {
+    langs
+        .entry(format!("{:?}", fs.language))
+        .or_insert_with(|| LangSummary::new(fs.language.clone()))
+        .totals
+        .add(fs);
+}
+
+fn merge_langs(into: &mut LangMap, from: LangMap) {
+    for (name, summary) in from {
+        match into.get_mut(&name) {
+            Some(existing) => existing.totals.merge(&summary.totals),
+            None => {
+                into.insert(name, summary);
             }
         }
     }
+}
 
-    // Sort language summaries by total_lines descending
-    let mut by_language: Vec<LangSummary> = lang_map.into_values().collect();
-    by_language.sort_by(|a, b| b.total_lines.cmp(&a.total_lines));
+/// Language summaries sorted by total_lines descending.
+pub(crate) fn sorted_langs(langs: LangMap) -> Vec<LangSummary> {
+    let mut by_language: Vec<LangSummary> = langs.into_values().collect();
+    by_language.sort_by_key(|s| std::cmp::Reverse(s.totals.total_lines));
+    by_language
+}
+
+/// Running aggregation state. Files are folded in one at a time, so a
+/// parallel walk can keep one accumulator per thread and merge them at the
+/// end instead of holding every `FileStats`.
+#[derive(Debug, Clone, Default)]
+pub struct Accumulator {
+    by_dir: bool,
+    roots: Vec<PathBuf>,
+    total: Totals,
+    langs: LangMap,
+    dirs: HashMap<PathBuf, LangMap>,
+    /// One slot per root, only used with several roots
+    root_slots: Vec<(Totals, LangMap)>,
+    marker_hits: Vec<MarkerHit>,
+}
 
-    // Build directory summaries (skip empty dirs)
-    let mut by_directory: Vec<DirSummary> = dir_map
+impl Accumulator {
+    pub fn new(by_dir: bool, roots: &[PathBuf]) -> Self {
+        let root_slots = if roots.len() > 1 {
+            vec![Default::default(); roots.len()]
+        } else {
+            Vec::new()
+        };
+        Accumulator {
+            by_dir,
+            roots: roots.to_vec(),
+            root_slots,
+            ..Accumulator::default()
+        }
+    }
+
+    pub fn add(&mut self, fs: &FileStats) {
+        // Root aggregation: a file belongs to the first root containing it
+        if !self.root_slots.is_empty()
+            && let Some(i) = self.roots.iter().position(|r| fs.path.starts_with(r))
+        {
+            let (root_total, root_langs) = &mut self.root_slots[i];
+            root_total.add(fs);
+            add_to_langs(root_langs, fs);
+        }
+
+        add_to_langs(&mut self.langs, fs);
+        self.total.add(fs);
+        self.marker_hits.extend_from_slice(&fs.marker_hits);
+
+        if self.by_dir
+            && let Some(parent) = fs.path.parent()
+        {
+            add_to_langs(self.dirs.entry(parent.to_path_buf()).or_default(), fs);
+        }
+    }
+
+    pub fn merge(&mut self, other: Accumulator) {
+        self.total.merge(&other.total);
+        self.marker_hits.extend(other.marker_hits);
+        merge_langs(&mut self.langs, other.langs);
+        for (dir, langs) in other.dirs {
+            merge_langs(self.dirs.entry(dir).or_default(), langs);
+        }
+        for (slot, (total, langs)) in self.root_slots.iter_mut().zip(other.root_slots) {
+            slot.0.merge(&total);
+            merge_langs(&mut slot.1, langs);
+        }
+    }
+
+    pub fn finish(mut self, meta: Meta) -> Report {
+        let by_language = sorted_langs(self.langs);
+        self.marker_hits
+            .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+
+        // Build directory summaries (skip empty dirs)
+        let mut by_directory: Vec<DirSummary> = self
+            .dirs
+            .into_iter()
+            .filter(|(_, m)| !m.is_empty())
+            .map(|(path, by_language)| DirSummary { path, by_language })
+            .collect();
+        by_directory.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let by_root: Vec<RootSummary> = self
+            .roots
+            .into_iter()
+            .zip(self.root_slots)
+            .map(|(root, (total, langs))| RootSummary {
+                root,
+                total,
+                by_language: sorted_langs(langs),
+            })
+            .collect();
+
+        Report {
+            schema_version: REPORT_SCHEMA_VERSION,
+            meta,
+            total: self.total,
+            by_language,
+            by_directory,
+            by_file: Vec::new(),
+            marker_hits: self.marker_hits,
+            by_root,
+            by_package: Vec::new(),
+            by_owner: Vec::new(),
+            unowned_files: Vec::new(),
+            by_license: Vec::new(),
+            unlicensed_files: Vec::new(),
+            churn: None,
+            duplicates: None,
+            baseline: None,
+            estimate: None,
+        }
+    }
+}
+
+/// Attributes every file to its nearest package. Packages are sorted by path;
+/// files outside every package come last.
+pub fn aggregate_by_package(stats: &[FileStats], index: &PackageIndex) -> Vec<PackageSummary> {
+    let packages = index.packages();
+    // Slot `packages.len()` collects files without a package
+    let mut slots: Vec<(Totals, LangMap)> = vec![Default::default(); packages.len() + 1];
+
+    for fs in stats {
+        let i = index.package_of(&fs.path).unwrap_or(packages.len());
+        let (total, langs) = &mut slots[i];
+        total.add(fs);
+        add_to_langs(langs, fs);
+    }
+
+    let mut by_package: Vec<PackageSummary> = slots
         .into_iter()
-        .filter(|(_, m)| !m.is_empty())
-        .map(|(path, by_language)| DirSummary { path, by_language })
+        .enumerate()
+        .filter(|(_, (total, _))| total.files > 0)
+        .map(|(i, (total, langs))| PackageSummary {
+            package: packages.get(i).cloned(),
+            total,
+            by_language: sorted_langs(langs),
+        })
         .collect();
-    by_directory.sort_by(|a, b| a.path.cmp(&b.path));
+    by_package.sort_by(|a, b| match (&a.package, &b.package) {
+        (Some(a), Some(b)) => a.path.cmp(&b.path),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    by_package
+}
 
-    Report {
-        total: global,
-        by_language,
-        by_directory,
+/// Attributes every file to its CODEOWNERS owners. Owners are sorted by
+/// total lines descending; also returns the unowned files, sorted by path.
+pub fn aggregate_by_owner(
+    stats: &[FileStats],
+    owners: &CodeOwners,
+) -> (Vec<OwnerSummary>, Vec<PathBuf>) {
+    let mut owner_map: HashMap<String, (Totals, LangMap)> = HashMap::new();
+    let mut unowned: Vec<PathBuf> = Vec::new();
+
+    for fs in stats {
+        let file_owners = owners.owners_of(&fs.path);
+        if file_owners.is_empty() {
+            unowned.push(fs.path.clone());
+            continue;
+        }
+        for owner in file_owners {
+            let (total, langs) = owner_map.entry(owner.clone()).or_default();
+            total.add(fs);
+            add_to_langs(langs, fs);
+        }
     }
+
+    let mut by_owner: Vec<OwnerSummary> = owner_map
+        .into_iter()
+        .map(|(owner, (total, langs))| OwnerSummary {
+            owner,
+            total,
+            by_language: sorted_langs(langs),
+        })
+        .collect();
+    by_owner.sort_by(|a, b| {
+        b.total
+            .total_lines
+            .cmp(&a.total.total_lines)
+            .then_with(|| a.owner.cmp(&b.owner))
+    });
+    unowned.sort();
+    (by_owner, unowned)
+}
+
+/// Groups files by the license detected in their header.
+pub fn aggregate_by_license(stats: &[FileStats]) -> (Vec<LicenseSummary>, Vec<PathBuf>) {
+    let mut license_map: HashMap<String, (Totals, LangMap)> = HashMap::new();
+    let mut unlicensed: Vec<PathBuf> = Vec::new();
+
+    for fs in stats {
+        let Some(license) = &fs.license else {
+            unlicensed.push(fs.path.clone());
+            continue;
+        };
+        let (total, langs) = license_map.entry(license.clone()).or_default();
+        total.add(fs);
+        add_to_langs(langs, fs);
+    }
+
+    let mut by_license: Vec<LicenseSummary> = license_map
+        .into_iter()
+        .map(|(license, (total, langs))| LicenseSummary {
+            license,
+            total,
+            by_language: sorted_langs(langs),
+        })
+        .collect();
+    by_license.sort_by(|a, b| {
+        b.total
+            .files
+            .cmp(&a.total.files)
+            .then_with(|| a.license.cmp(&b.license))
+    });
+    unlicensed.sort();
+    (by_license, unlicensed)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+
+    fn aggregate(stats: Vec<FileStats>, by_dir: bool, meta: Meta) -> Report {
+        let mut acc = Accumulator::new(by_dir, &meta.roots);
+        for fs in &stats {
+            acc.add(fs);
+        }
+        acc.finish(meta)
+    }
 
     fn make_stats(lang: Language, total: u64, code: u64, blank: u64, comment: u64) -> FileStats {
         FileStats {
@@ -129,6 +474,16 @@ mod tests {
             code_lines: code,
             blank_lines: blank,
             comment_lines: comment,
+            doc_lines: 0,
+            test_code_lines: 0,
+            production_code_lines: code,
+            text: TextStats::default(),
+            markers: MarkerCounts::new(),
+            marker_hits: Vec::new(),
+            license: None,
+            hygiene: Hygiene::default(),
+            cells: 0,
+            symbols: Symbols::default(),
         }
     }
 
@@ -139,23 +494,23 @@ mod tests {
             make_stats(Language::Rust, 5, 4, 1, 0),
             make_stats(Language::Python, 8, 6, 1, 1),
         ];
-        let report = aggregate(stats, false);
+        let report = aggregate(stats, false, Meta::default());
         assert_eq!(report.by_language.len(), 2);
         let rust = report
             .by_language
             .iter()
             .find(|s| s.language == Language::Rust)
             .unwrap();
-        assert_eq!(rust.files, 2);
-        assert_eq!(rust.total_lines, 15);
-        assert_eq!(rust.code_lines, 11);
+        assert_eq!(rust.totals.files, 2);
+        assert_eq!(rust.totals.total_lines, 15);
+        assert_eq!(rust.totals.code_lines, 11);
         let python = report
             .by_language
             .iter()
             .find(|s| s.language == Language::Python)
             .unwrap();
-        assert_eq!(python.files, 1);
-        assert_eq!(python.total_lines, 8);
+        assert_eq!(python.totals.files, 1);
+        assert_eq!(python.totals.total_lines, 8);
     }
 
     #[test]
@@ -164,8 +519,119 @@ mod tests {
             make_stats(Language::Rust, 10, 7, 2, 1),
             make_stats(Language::Python, 8, 6, 1, 1),
         ];
-        let report = aggregate(stats, false);
+        let report = aggregate(stats, false, Meta::default());
         assert_eq!(report.total.files, 2);
         assert_eq!(report.total.total_lines, 18);
     }
+
+    #[test]
+    fn test_aggregate_test_code_split() {
+        let mut test_file = make_stats(Language::Rust, 10, 8, 1, 1);
+        test_file.test_code_lines = 8;
+        test_file.production_code_lines = 0;
+        let stats = vec![test_file, make_stats(Language::Rust, 5, 4, 1, 0)];
+        let report = aggregate(stats, false, Meta::default());
+        assert_eq!(report.total.test_code_lines, 8);
+        assert_eq!(report.total.production_code_lines, 4);
+        assert_eq!(report.by_language[0].totals.test_code_lines, 8);
+    }
+
+    #[test]
+    fn test_aggregate_by_root() {
+        let mut a = make_stats(Language::Rust, 10, 7, 2, 1);
+        a.path = PathBuf::from("services/a/main.rs");
+        let mut b = make_stats(Language::Python, 8, 6, 1, 1);
+        b.path = PathBuf::from("libs/util.py");
+        let meta = Meta {
+            roots: vec![PathBuf::from("services/a"), PathBuf::from("libs")],
+            ..Meta::default()
+        };
+        let report = aggregate(vec![a, b], false, meta);
+        assert_eq!(report.by_root.len(), 2);
+        assert_eq!(report.by_root[0].root, PathBuf::from("services/a"));
+        assert_eq!(report.by_root[0].total.total_lines, 10);
+        assert_eq!(report.by_root[1].total.files, 1);
+        assert_eq!(report.by_root[1].by_language[0].language, Language::Python);
+        assert_eq!(report.total.files, 2);
+    }
+
+    #[test]
+    fn test_single_root_has_no_breakdown() {
+        let meta = Meta {
+            roots: vec![PathBuf::from("src")],
+            ..Meta::default()
+        };
+        let report = aggregate(vec![make_stats(Language::Rust, 1, 1, 0, 0)], false, meta);
+        assert!(report.by_root.is_empty());
+    }
+
+    #[test]
+    fn test_aggregate_by_owner() {
+        let owners =
+            CodeOwners::parse("*.rs @core\n/web/ @web @design\n", Path::new("/r")).unwrap();
+        let mut rs = make_stats(Language::Rust, 10, 7, 2, 1);
+        rs.path = PathBuf::from("/r/src/lib.rs");
+        let mut js = make_stats(Language::JavaScript, 4, 4, 0, 0);
+        js.path = PathBuf::from("/r/web/app.js");
+        let mut py = make_stats(Language::Python, 3, 3, 0, 0);
+        py.path = PathBuf::from("/r/tools/gen.py");
+
+        let (by_owner, unowned) = aggregate_by_owner(&[rs, js, py], &owners);
+
+        assert_eq!(by_owner.len(), 3);
+        assert_eq!(by_owner[0].owner, "@core");
+        assert_eq!(by_owner[0].total.total_lines, 10);
+        assert_eq!(by_owner[1].owner, "@design");
+        assert_eq!(by_owner[2].owner, "@web");
+        assert_eq!(by_owner[2].by_language[0].language, Language::JavaScript);
+        assert_eq!(unowned, vec![PathBuf::from("/r/tools/gen.py")]);
+    }
+
+    #[test]
+    fn test_aggregate_by_license() {
+        let mut a = make_stats(Language::Rust, 10, 7, 2, 1);
+        a.path = PathBuf::from("a.rs");
+        a.license = Some("MIT".into());
+        let mut b = make_stats(Language::Python, 3, 3, 0, 0);
+        b.path = PathBuf::from("b.py");
+        b.license = Some("MIT".into());
+        let mut c = make_stats(Language::Go, 4, 4, 0, 0);
+        c.path = PathBuf::from("c.go");
+        c.license = Some("Apache-2.0".into());
+        let mut d = make_stats(Language::Go, 2, 2, 0, 0);
+        d.path = PathBuf::from("d.go");
+
+        let (by_license, unlicensed) = aggregate_by_license(&[a, b, c, d]);
+
+        assert_eq!(by_license.len(), 2);
+        assert_eq!(by_license[0].license, "MIT");
+        assert_eq!(by_license[0].total.files, 2);
+        assert_eq!(by_license[0].by_language.len(), 2);
+        assert_eq!(by_license[1].license, "Apache-2.0");
+        assert_eq!(unlicensed, vec![PathBuf::from("d.go")]);
+    }
+
+    #[test]
+    fn test_merged_accumulators_match_single_pass() {
+        let stats = vec![
+            make_stats(Language::Rust, 10, 7, 2, 1),
+            make_stats(Language::Python, 8, 6, 1, 1),
+            make_stats(Language::Rust, 5, 4, 1, 0),
+        ];
+        let single = aggregate(stats.clone(), true, Meta::default());
+
+        let mut left = Accumulator::new(true, &[]);
+        left.add(&stats[0]);
+        let mut right = Accumulator::new(true, &[]);
+        right.add(&stats[1]);
+        right.add(&stats[2]);
+        left.merge(right);
+        let merged = left.finish(Meta::default());
+
+        assert_eq!(merged.total.total_lines, single.total.total_lines);
+        assert_eq!(merged.by_language.len(), 2);
+        assert_eq!(merged.by_language[0].totals.total_lines, 15);
+        assert_eq!(merged.by_language[0].totals.files, 2);
+        assert_eq!(merged.by_directory[0].by_language["Rust"].totals.files, 2);
+    }
 }
@@ -0,0 +1,481 @@
+use crate::aggregator::{Report, Totals};
+use crate::counter::FileStats;
+use crate::language::{Language, language_from_name};
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+
+/// Conventional name of the baseline file.
+pub const BASELINE_FILE: &str = "qcount-baseline.json";
+
+/// Decimal places kept for ratio metrics such as `comment_density`.
+const RATIO_DECIMALS: i32 = 4;
+
+/// Metrics a baseline rule can ratchet; the names match the `Report` fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Files,
+    TotalLines,
+    CodeLines,
+    CommentLines,
+    BlankLines,
+    TestCodeLines,
+    ProductionCodeLines,
+    /// Comment lines / (code + comment lines)
+    CommentDensity,
+}
+
+impl Metric {
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Files => "files",
+            Metric::TotalLines => "total_lines",
+            Metric::CodeLines => "code_lines",
+            Metric::CommentLines => "comment_lines",
+            Metric::BlankLines => "blank_lines",
+            Metric::TestCodeLines => "test_code_lines",
+            Metric::ProductionCodeLines => "production_code_lines",
+            Metric::CommentDensity => "comment_density",
+        }
+    }
+
+    fn of(self, t: &Totals) -> f64 {
+        match self {
+            Metric::Files => t.files as f64,
+            Metric::TotalLines => t.total_lines as f64,
+            Metric::CodeLines => t.code_lines as f64,
+            Metric::CommentLines => t.comment_lines as f64,
+            Metric::BlankLines => t.blank_lines as f64,
+            Metric::TestCodeLines => t.test_code_lines as f64,
+            Metric::ProductionCodeLines => t.production_code_lines as f64,
+            Metric::CommentDensity => {
+                let lines = t.code_lines + t.comment_lines;
+                if lines == 0 {
+                    0.0
+                } else {
+                    t.comment_lines as f64 / lines as f64
+                }
+            }
+        }
+    }
+}
+
+/// Direction a rule's value may move in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Ratchet {
+    /// The value is a ceiling: growing is a regression
+    Max,
+    /// The value is a floor: shrinking is a regression
+    Min,
+}
+
+impl Ratchet {
+    pub fn name(self) -> &'static str {
+        match self {
+            Ratchet::Max => "max",
+            Ratchet::Min => "min",
+        }
+    }
+
+    /// Rounds a ratio to `RATIO_DECIMALS` toward the lenient side, so a
+    /// stored value never fails the run that wrote it.
+    fn round(self, value: f64) -> f64 {
+        let scale = 10f64.powi(RATIO_DECIMALS);
+        match self {
+            Ratchet::Max => (value * scale).ceil() / scale,
+            Ratchet::Min => (value * scale).floor() / scale,
+        }
+    }
+}
+
+/// One ratcheted metric over the files under `path` (relative to the
+/// counted root), optionally restricted to one language.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub metric: Metric,
+    pub ratchet: Ratchet,
+    /// Recorded value; a rule without one is filled by `--update-baseline`
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_value"
+    )]
+    pub value: Option<f64>,
+    #[serde(skip)]
+    parsed_language: Option<Language>,
+}
+
+/// Contents of `qcount-baseline.json`: ratchet rules over the scopes of the
+/// JSON `Report`. `--update-baseline` seeds a missing file from the report
+/// (see [`Baseline::from_report`]); rules can then be edited or added by hand.
+///
+/// ```json
+/// {
+///   "rules": [
+///     { "path": "src/core", "metric": "comment_density", "ratchet": "min", "value": 0.18 },
+///     { "path": "legacy", "language": "Rust", "metric": "code_lines", "ratchet": "max" }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    pub rules: Vec<Rule>,
+}
+
+/// Outcome of one rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Same as the recorded value
+    Ok,
+    /// Better than the recorded value
+    Improved,
+    /// Worse than the recorded value
+    Regressed,
+    /// The rule has no recorded value yet
+    Unset,
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Improved => "improved",
+            Status::Regressed => "regressed",
+            Status::Unset => "unset",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct RuleResult {
+    pub path: Option<PathBuf>,
+    pub language: Option<String>,
+    pub metric: Metric,
+    pub ratchet: Ratchet,
+    /// Value recorded in the baseline file before this run
+    #[serde(serialize_with = "serialize_value")]
+    pub baseline: Option<f64>,
+    #[serde(serialize_with = "serialize_number")]
+    pub current: f64,
+    pub status: Status,
+}
+
+impl RuleResult {
+    /// `path [language]` label of the rule; `.` stands for the whole root.
+    pub fn scope(&self) -> String {
+        let path = self
+            .path
+            .as_ref()
+            .map_or(".".to_string(), |p| p.display().to_string());
+        match &self.language {
+            Some(language) => format!("{} [{}]", path, language),
+            None => path,
+        }
+    }
+}
+
+/// Result of checking the counted files against a baseline file.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct BaselineCheck {
+    pub file: PathBuf,
+    pub rules: Vec<RuleResult>,
+    /// Rules whose metric got worse
+    pub regressed: u64,
+    /// `--update-baseline` rewrote the file with tightened values
+    pub updated: bool,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Baseline> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let mut baseline: Baseline = serde_json::from_str(&content)
+            .with_context(|| format!("invalid {}", path.display()))?;
+        for rule in &mut baseline.rules {
+            if let Some(name) = &rule.language {
+                let Some(language) = language_from_name(name) else {
+                    bail!("unknown language {:?} in {}", name, path.display());
+                };
+                rule.parsed_language = Some(language);
+            }
+        }
+        Ok(baseline)
+    }
+
+    /// Seeds a baseline from `report`: a `comment_density` floor for the
+    /// whole tree, every language and every `by_directory` entry. Values are
+    /// left unset for `tighten` to record; ceilings such as `code_lines` for
+    /// a deprecated directory are added by hand.
+    pub fn from_report(report: &Report) -> Baseline {
+        let rule = |path: Option<PathBuf>, language: Option<&Language>| Rule {
+            path,
+            language: language.map(|l| l.name().to_string()),
+            metric: Metric::CommentDensity,
+            ratchet: Ratchet::Min,
+            value: None,
+            parsed_language: language.cloned(),
+        };
+        let mut rules = vec![rule(None, None)];
+        for lang in &report.by_language {
+            rules.push(rule(None, Some(&lang.language)));
+        }
+        for dir in &report.by_directory {
+            let relative = report
+                .meta
+                .roots
+                .iter()
+                .find_map(|root| dir.path.strip_prefix(root).ok())
+                .unwrap_or(&dir.path);
+            if !relative.as_os_str().is_empty() {
+                rules.push(rule(Some(relative.to_path_buf()), None));
+            }
+        }
+        Baseline { rules }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        std::fs::write(path, json).with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    /// Evaluates every rule over `files`, whose paths are matched relative
+    /// to the root they were found under.
+    pub fn check(&self, file: &Path, roots: &[PathBuf], files: &[FileStats]) -> BaselineCheck {
+        let rules: Vec<RuleResult> = self
+            .rules
+            .iter()
+            .map(|rule| rule.check(roots, files))
+            .collect();
+        BaselineCheck {
+            file: file.to_path_buf(),
+            regressed: rules
+                .iter()
+                .filter(|r| r.status == Status::Regressed)
+                .count() as u64,
+            rules,
+            updated: false,
+        }
+    }
+
+    /// Moves the recorded values of improved and unset rules to the current
+    /// ones. Regressed rules keep their value. Returns whether anything changed.
+    pub fn tighten(&mut self, check: &BaselineCheck) -> bool {
+        let mut changed = false;
+        for (rule, result) in self.rules.iter_mut().zip(&check.rules) {
+            if matches!(result.status, Status::Improved | Status::Unset) {
+                rule.value = Some(result.current);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+impl Rule {
+    fn check(&self, roots: &[PathBuf], files: &[FileStats]) -> RuleResult {
+        let prefix = self
+            .path
+            .as_deref()
+            .map(|p| p.strip_prefix(".").unwrap_or(p));
+        let mut totals = Totals::default();
+        for fs in files {
+            let relative = roots
+                .iter()
+                .find_map(|root| fs.path.strip_prefix(root).ok())
+                .unwrap_or(&fs.path);
+            if prefix.is_some_and(|p| !relative.starts_with(p)) {
+                continue;
+            }
+            if self
+                .parsed_language
+                .as_ref()
+                .is_some_and(|l| *l != fs.language)
+            {
+                continue;
+            }
+            totals.add(fs);
+        }
+
+        let mut current = self.metric.of(&totals);
+        if self.metric == Metric::CommentDensity {
+            current = self.ratchet.round(current);
+        }
+        let status = match self.value {
+            None => Status::Unset,
+            Some(value) if current == value => Status::Ok,
+            Some(value) if (current > value) == (self.ratchet == Ratchet::Max) => Status::Regressed,
+            Some(_) => Status::Improved,
+        };
+        RuleResult {
+            path: self.path.clone(),
+            language: self.language.clone(),
+            metric: self.metric,
+            ratchet: self.ratchet,
+            baseline: self.value,
+            current,
+            status,
+        }
+    }
+}
+
+/// Formats a metric value: counts without decimals, ratios with
+/// `RATIO_DECIMALS`.
+pub fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.*}", RATIO_DECIMALS as usize, value)
+    }
+}
+
+/// Writes whole numbers as JSON integers (`1200`, not `1200.0`).
+fn serialize_number<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    if value.fract() == 0.0 && value.abs() < 2f64.powi(53) {
+        serializer.serialize_i64(*value as i64)
+    } else {
+        serializer.serialize_f64(*value)
+    }
+}
+
+fn serialize_value<S: Serializer>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serialize_number(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregator::{Accumulator, Meta};
+    use crate::counter::{CountOptions, count_content};
+
+    fn file(path: &str, content: &str, language: Language) -> FileStats {
+        count_content(content, Path::new(path), language, &CountOptions::default())
+    }
+
+    fn files() -> Vec<FileStats> {
+        vec![
+            file("./src/core/a.rs", "// doc\nfn a() {}\n", Language::Rust),
+            file("./src/core/b.py", "x = 1\ny = 2\n", Language::Python),
+            file(
+                "./legacy/old.rs",
+                "fn old() {}\nfn older() {}\n",
+                Language::Rust,
+            ),
+        ]
+    }
+
+    fn baseline(json: &str) -> Baseline {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BASELINE_FILE);
+        std::fs::write(&path, json).unwrap();
+        Baseline::load(&path).unwrap()
+    }
+
+    #[test]
+    fn test_check_statuses() {
+        let baseline = baseline(
+            r#"{"rules": [
+                {"path": "legacy", "metric": "code_lines", "ratchet": "max", "value": 2},
+                {"path": "./src/core", "language": "rust", "metric": "comment_density",
+                 "ratchet": "min", "value": 0.6},
+                {"path": "src", "metric": "files", "ratchet": "max", "value": 3},
+                {"metric": "code_lines", "ratchet": "max"}
+            ]}"#,
+        );
+        let check = baseline.check(Path::new("b.json"), &[PathBuf::from(".")], &files());
+
+        let statuses: Vec<Status> = check.rules.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                Status::Ok,
+                Status::Regressed,
+                Status::Improved,
+                Status::Unset
+            ]
+        );
+        assert_eq!(check.rules[1].current, 0.5);
+        assert_eq!(check.rules[1].scope(), "./src/core [rust]");
+        assert_eq!(check.rules[3].current, 5.0);
+        assert_eq!(check.regressed, 1);
+    }
+
+    #[test]
+    fn test_tighten_keeps_regressions() {
+        let mut baseline = baseline(
+            r#"{"rules": [
+                {"path": "legacy", "metric": "code_lines", "ratchet": "max", "value": 1},
+                {"path": "src", "metric": "files", "ratchet": "max", "value": 3},
+                {"metric": "comment_density", "ratchet": "min"}
+            ]}"#,
+        );
+        let check = baseline.check(Path::new("b.json"), &[PathBuf::from(".")], &files());
+        assert!(baseline.tighten(&check));
+
+        let values: Vec<Option<f64>> = baseline.rules.iter().map(|r| r.value).collect();
+        // 1 comment / 6 lines, rounded down for a floor
+        assert_eq!(values, [Some(1.0), Some(2.0), Some(0.1666)]);
+
+        let json = serde_json::to_string(&baseline).unwrap();
+        assert!(json.contains(r#""value":2}"#));
+        assert!(json.contains(r#""value":0.1666}"#));
+        let check = baseline.check(Path::new("b.json"), &[PathBuf::from(".")], &files());
+        assert!(!baseline.tighten(&check));
+    }
+
+    #[test]
+    fn test_from_report_seeds_density_floors() {
+        let meta = Meta {
+            roots: vec![PathBuf::from(".")],
+            ..Meta::default()
+        };
+        let mut acc = Accumulator::new(true, &meta.roots);
+        for fs in &files() {
+            acc.add(fs);
+        }
+        let mut baseline = Baseline::from_report(&acc.finish(meta));
+
+        let check = baseline.check(Path::new("b.json"), &[PathBuf::from(".")], &files());
+        let scopes: Vec<String> = check.rules.iter().map(|r| r.scope()).collect();
+        assert_eq!(
+            scopes,
+            [".", ". [Rust]", ". [Python]", "legacy", "src/core"]
+        );
+        assert!(check.rules.iter().all(|r| r.status == Status::Unset));
+
+        assert!(baseline.tighten(&check));
+        let check = baseline.check(Path::new("b.json"), &[PathBuf::from(".")], &files());
+        assert!(check.rules.iter().all(|r| r.status == Status::Ok));
+    }
+
+    #[test]
+    fn test_unknown_language_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BASELINE_FILE);
+        std::fs::write(
+            &path,
+            r#"{"rules": [{"language": "Cobol", "metric": "files", "ratchet": "max"}]}"#,
+        )
+        .unwrap();
+        assert!(Baseline::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(1200.0), "1200");
+        assert_eq!(format_value(0.1666), "0.1666");
+    }
+}
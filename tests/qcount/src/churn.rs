@@ -0,0 +1,436 @@
+use crate::counter::{BlankPolicy, LineKind, line_kinds};
+use crate::language::{Language, language_of};
+use crate::notebook;
+use crate::walker::{self, WalkOptions};
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Changed lines of one kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, JsonSchema)]
+pub struct LineChurn {
+    pub added: u64,
+    pub removed: u64,
+    /// Lines replaced within a hunk (counted once, not as added + removed)
+    pub modified: u64,
+}
+
+/// Churn split by line kind, as classified by the counter.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct KindChurn {
+    pub code: LineChurn,
+    pub comment: LineChurn,
+    pub blank: LineChurn,
+    /// Notebook markdown lines
+    pub doc: LineChurn,
+}
+
+impl KindChurn {
+    fn kind_mut(&mut self, kind: LineKind) -> &mut LineChurn {
+        match kind {
+            LineKind::Code => &mut self.code,
+            LineKind::Comment => &mut self.comment,
+            LineKind::Blank => &mut self.blank,
+            LineKind::Doc => &mut self.doc,
+        }
+    }
+
+    fn merge(&mut self, other: &KindChurn) {
+        for (into, from) in [
+            (&mut self.code, &other.code),
+            (&mut self.comment, &other.comment),
+            (&mut self.blank, &other.blank),
+            (&mut self.doc, &other.doc),
+        ] {
+            into.added += from.added;
+            into.removed += from.removed;
+            into.modified += from.modified;
+        }
+    }
+
+    /// Adds one hunk: per kind, removed and added lines pair up as modified
+    /// and the remainder counts as added or removed.
+    fn add_hunk(&mut self, removed: &[LineKind], added: &[LineKind]) {
+        for kind in [
+            LineKind::Code,
+            LineKind::Comment,
+            LineKind::Blank,
+            LineKind::Doc,
+        ] {
+            let r = removed.iter().filter(|&&k| k == kind).count() as u64;
+            let a = added.iter().filter(|&&k| k == kind).count() as u64;
+            let modified = r.min(a);
+            let churn = self.kind_mut(kind);
+            churn.modified += modified;
+            churn.added += a - modified;
+            churn.removed += r - modified;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct LangChurn {
+    pub language: Language,
+    /// Changed files
+    pub files: u64,
+    pub churn: KindChurn,
+}
+
+/// Line churn between a git revision and the working tree (`--since`).
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Churn {
+    /// Base revision as given on the command line
+    pub base: String,
+    pub files: u64,
+    pub total: KindChurn,
+    pub by_language: Vec<LangChurn>,
+}
+
+/// Compares the working tree under `roots` with `base`. Tracked changes come
+/// from `git diff`; untracked, non-ignored files count as added. Renames are
+/// treated as a removal plus an addition.
+pub fn since(
+    base: &str,
+    roots: &[PathBuf],
+    walk: &WalkOptions,
+    overrides: &HashMap<String, Language>,
+    blank: BlankPolicy,
+) -> Result<Churn> {
+    let top = PathBuf::from(
+        String::from_utf8_lossy(&git(&roots[0], ["rev-parse", "--show-toplevel"])?).trim(),
+    );
+    git(
+        &top,
+        [
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", base),
+        ],
+    )
+    .with_context(|| format!("unknown revision: {}", base))?;
+
+    let mut pathspecs = Vec::new();
+    let mut canonical = Vec::new();
+    for root in roots {
+        let absolute = std::fs::canonicalize(root)
+            .with_context(|| format!("cannot resolve {}", root.display()))?;
+        let Ok(relative) = absolute.strip_prefix(&top) else {
+            bail!(
+                "{} is outside the git repository {}",
+                root.display(),
+                top.display()
+            );
+        };
+        pathspecs.push(format!(":(top,literal){}", relative.display()));
+        canonical.push(absolute);
+    }
+    let keep = walker::path_filter(&canonical, walk)?;
+
+    // (status, path relative to `top`)
+    let mut changes: Vec<(u8, PathBuf)> = Vec::new();
+    let diff = git_with_paths(
+        &top,
+        &["diff", "--name-status", "-z", "--no-renames", base],
+        &pathspecs,
+    )?;
+    let mut fields = diff.split(|&b| b == 0).filter(|f| !f.is_empty());
+    while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+        changes.push((
+            status[0],
+            PathBuf::from(String::from_utf8_lossy(path).as_ref()),
+        ));
+    }
+    let untracked = git_with_paths(
+        &top,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+        &pathspecs,
+    )?;
+    for path in untracked.split(|&b| b == 0).filter(|f| !f.is_empty()) {
+        changes.push((b'A', PathBuf::from(String::from_utf8_lossy(path).as_ref())));
+    }
+
+    let mut langs: HashMap<String, LangChurn> = HashMap::new();
+    let mut total = KindChurn::default();
+    let mut files = 0;
+    for (status, path) in changes {
+        let Some(language) = language_of(&path, overrides) else {
+            continue;
+        };
+        if !keep(&top.join(&path)) {
+            continue;
+        }
+        let churn = file_churn(&top, base, status, &path, &language, blank)?;
+        files += 1;
+        total.merge(&churn);
+        let entry = langs
+            .entry(format!("{:?}", language))
+            .or_insert_with(|| LangChurn {
+                language,
+                files: 0,
+                churn: KindChurn::default(),
+            });
+        entry.files += 1;
+        entry.churn.merge(&churn);
+    }
+
+    let mut by_language: Vec<LangChurn> = langs.into_values().collect();
+    by_language.sort_by(|a, b| {
+        b.files
+            .cmp(&a.files)
+            .then_with(|| a.language.name().cmp(b.language.name()))
+    });
+    Ok(Churn {
+        base: base.to_string(),
+        files,
+        total,
+        by_language,
+    })
+}
+
+fn file_churn(
+    top: &Path,
+    base: &str,
+    status: u8,
+    path: &Path,
+    language: &Language,
+    blank: BlankPolicy,
+) -> Result<KindChurn> {
+    let old_content = || -> Result<String> {
+        let spec = format!("{}:{}", base, path.display());
+        let content = git(top, ["show", &spec])?;
+        Ok(String::from_utf8_lossy(&content).into_owned())
+    };
+    let new_content = || -> Result<String> {
+        let content = std::fs::read(top.join(path))
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        Ok(String::from_utf8_lossy(&content).into_owned())
+    };
+
+    // Notebook JSON lines say nothing about the cells, so both revisions
+    // are parsed and their cell sources diffed instead
+    if *language == Language::Jupyter {
+        let lines = |content: Result<String>| -> Result<Vec<(String, LineKind)>> {
+            notebook::source_lines(&content?, blank)
+                .with_context(|| format!("cannot count {}", path.display()))
+        };
+        let old = if status == b'A' {
+            Vec::new()
+        } else {
+            lines(old_content())?
+        };
+        let new = if status == b'D' {
+            Vec::new()
+        } else {
+            lines(new_content())?
+        };
+        return Ok(diff_lines(&old, &new));
+    }
+
+    let old = || -> Result<Vec<LineKind>> { Ok(line_kinds(&old_content()?, language, blank)) };
+    let new = || -> Result<Vec<LineKind>> { Ok(line_kinds(&new_content()?, language, blank)) };
+    let mut churn = KindChurn::default();
+    match status {
+        b'A' => churn.add_hunk(&[], &new()?),
+        b'D' => churn.add_hunk(&old()?, &[]),
+        _ => {
+            let (old, new) = (old()?, new()?);
+            let patch = git_with_paths(
+                top,
+                &["diff", "-U0", "--no-color", "--no-ext-diff", base],
+                &[format!(":(top,literal){}", path.display())],
+            )?;
+            for hunk in String::from_utf8_lossy(&patch)
+                .lines()
+                .filter_map(parse_hunk)
+            {
+                churn.add_hunk(slice(&old, hunk.0), slice(&new, hunk.1));
+            }
+        }
+    }
+    Ok(churn)
+}
+
+/// Lines above which `diff_lines` stops looking for common lines and
+/// treats the changed region as a single hunk.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Churn between two classified line lists, pairing changed regions into
+/// hunks around their longest common subsequence, as `git diff` would.
+fn diff_lines(old: &[(String, LineKind)], new: &[(String, LineKind)]) -> KindChurn {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a.0 == b.0).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a.0 == b.0)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+    let kinds = |lines: &[(String, LineKind)]| lines.iter().map(|l| l.1).collect::<Vec<_>>();
+
+    let mut churn = KindChurn::default();
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        churn.add_hunk(&kinds(old), &kinds(new));
+        return churn;
+    }
+
+    // common[i * width + j]: length of the LCS of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut common = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i * width + j] = if old[i].0 == new[j].0 {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i].0 == new[j].0 {
+            churn.add_hunk(&removed, &added);
+            removed.clear();
+            added.clear();
+            i += 1;
+            j += 1;
+        } else if j == new.len()
+            || (i < old.len() && common[(i + 1) * width + j] >= common[i * width + j + 1])
+        {
+            removed.push(old[i].1);
+            i += 1;
+        } else {
+            added.push(new[j].1);
+            j += 1;
+        }
+    }
+    churn.add_hunk(&removed, &added);
+    churn
+}
+
+/// 1-based start line and length of one side of a hunk.
+type Range = (usize, usize);
+
+/// Parses `@@ -a[,b] +c[,d] @@` into the old and new line ranges.
+fn parse_hunk(line: &str) -> Option<(Range, Range)> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, _) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    Some((parse_range(old)?, parse_range(new)?))
+}
+
+fn parse_range(range: &str) -> Option<Range> {
+    match range.split_once(',') {
+        Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+fn slice(kinds: &[LineKind], (start, len): Range) -> &[LineKind] {
+    if len == 0 {
+        return &[];
+    }
+    let from = (start - 1).min(kinds.len());
+    &kinds[from..(from + len).min(kinds.len())]
+}
+
+fn git<I, S>(dir: &Path, args: I) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git failed in {}: {}",
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+fn git_with_paths(dir: &Path, args: &[&str], pathspecs: &[String]) -> Result<Vec<u8>> {
+    let pathspecs = pathspecs.iter().map(String::as_str);
+    git(dir, args.iter().copied().chain(["--"]).chain(pathspecs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hunk() {
+        assert_eq!(
+            parse_hunk("@@ -3,2 +3 @@ fn main() {"),
+            Some(((3, 2), (3, 1)))
+        );
+        assert_eq!(parse_hunk("@@ -0,0 +1,4 @@"), Some(((0, 0), (1, 4))));
+        assert_eq!(parse_hunk("+++ b/src/lib.rs"), None);
+    }
+
+    #[test]
+    fn test_diff_lines_pairs_changed_regions() {
+        use LineKind::*;
+        let lines = |lines: &[(&str, LineKind)]| -> Vec<(String, LineKind)> {
+            lines.iter().map(|(l, k)| (l.to_string(), *k)).collect()
+        };
+        let old = lines(&[("# load", Comment), ("import os", Code), ("x = 1", Code)]);
+        let new = lines(&[
+            ("# load data", Comment),
+            ("import os", Code),
+            ("x = 2", Code),
+            ("", Blank),
+        ]);
+        let churn = diff_lines(&old, &new);
+        assert_eq!(churn.comment.modified, 1);
+        assert_eq!(churn.code.modified, 1);
+        assert_eq!(churn.code.added, 0);
+        assert_eq!(churn.blank.added, 1);
+
+        assert_eq!(diff_lines(&old, &old), KindChurn::default());
+        assert_eq!(diff_lines(&[], &old).code.added, 2);
+    }
+
+    #[test]
+    fn test_hunk_pairs_lines_by_kind() {
+        use LineKind::*;
+        let mut churn = KindChurn::default();
+        // A comment reworded, a code line replaced by two
+        churn.add_hunk(&[Comment, Code], &[Comment, Code, Code]);
+        assert_eq!(
+            churn.comment,
+            LineChurn {
+                added: 0,
+                removed: 0,
+                modified: 1
+            }
+        );
+        assert_eq!(
+            churn.code,
+            LineChurn {
+                added: 1,
+                removed: 0,
+                modified: 1
+            }
+        );
+
+        // A code line commented out
+        let mut churn = KindChurn::default();
+        churn.add_hunk(&[Code], &[Comment]);
+        assert_eq!(churn.code.removed, 1);
+        assert_eq!(churn.comment.added, 1);
+    }
+}
@@ -1,21 +1,213 @@
-use clap::Parser;
+use crate::config::OutputFormat;
+use crate::estimate::{CocomoMode, DEFAULT_OVERHEAD, DEFAULT_SALARY};
+use clap::{Parser, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "qcount", about = "Fast source code line counter")]
 pub struct Args {
-    /// 対象ディレクトリ（デフォルト: カレント）
-    pub path: Option<PathBuf>,
+    /// 対象ディレクトリ（複数指定可、デフォルト: カレント）
+    pub paths: Vec<PathBuf>,
 
-    /// JSON形式で出力
+    /// JSON形式で出力（--format json と同じ）
     #[arg(long)]
     pub json: bool,
 
+    /// 出力形式
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// 標準出力に加えてレポートをファイルにも書き出す（例: json=report.json、複数指定可）
+    #[arg(long, value_name = "FORMAT=PATH", value_parser = parse_output)]
+    pub output: Vec<Output>,
+
     /// ディレクトリ別集計を表示
-    #[arg(long)]
+    #[arg(long, overrides_with = "no_by_dir")]
     pub by_dir: bool,
 
-    /// 除外パターン（glob）
+    /// ディレクトリ別集計を表示しない（.qcount.toml の by_dir を打ち消す）
+    #[arg(long, overrides_with = "by_dir")]
+    pub no_by_dir: bool,
+
+    /// ファイル別集計を表示
+    #[arg(long, overrides_with = "no_by_file")]
+    pub by_file: bool,
+
+    /// ファイル別集計を表示しない（.qcount.toml の by_file を打ち消す）
+    #[arg(long, overrides_with = "by_file")]
+    pub no_by_file: bool,
+
+    /// パッケージ（Cargo/npm/Go/Python）別集計を表示
+    #[arg(long)]
+    pub by_package: bool,
+
+    /// CODEOWNERSのオーナー（チーム・ユーザー）別集計を表示
+    #[arg(long)]
+    pub by_owner: bool,
+
+    /// 除外パターン（glob、複数指定可）
     #[arg(long)]
     pub exclude: Vec<String>,
+
+    /// 対象に含めるパターン（glob、複数指定可。指定時は一致したファイルのみ）
+    #[arg(long)]
+    pub include: Vec<String>,
+
+    /// 隠しファイル・隠しディレクトリも対象にする
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// .gitignore / .ignore / .qcountignore を無視する
+    #[arg(long)]
+    pub no_ignore: bool,
+
+    /// シンボリックリンクをたどる
+    #[arg(short = 'L', long)]
+    pub follow: bool,
+
+    /// ファイルシステムの境界を越えない
+    #[arg(long)]
+    pub one_file_system: bool,
+
+    /// 走査する最大深さ
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// ハードリンクや内容が同一のファイルを1回だけ数え、スキップした重複を表示
+    #[arg(long)]
+    pub dedupe: bool,
+
+    /// この文字数を超える行を長い行として数える（デフォルト: 120）
+    #[arg(long)]
+    pub line_width: Option<usize>,
+
+    /// 空行・行頭/行末の判定を従来どおり `str::trim` で行う（BOM・ゼロ幅文字を空白扱いしない）
+    #[arg(long)]
+    pub strict_ascii_blank: bool,
+
+    /// 総行数と空行のみを高速に数える（コメントは判定せず、空行以外はすべてコード扱い）
+    #[arg(long)]
+    pub total_only: bool,
+
+    /// ファイル先頭のライセンスヘッダー（SPDX識別子・既知のライセンス文）を集計
+    #[arg(long)]
+    pub licenses: bool,
+
+    /// 改行コード（LF/CRLF/CR/混在）・末尾改行・行末空白・インデント（タブ/スペース）を監査
+    #[arg(long)]
+    pub hygiene: bool,
+
+    /// 関数・メソッド、型（struct/class/enum/interface）、モジュールの宣言数を数える
+    #[arg(long)]
+    pub symbols: bool,
+
+    /// コメント中のマーカーを数える（カンマ区切り、例: --markers TODO,FIXME,XXX）
+    #[arg(long, value_name = "TAGS", value_delimiter = ',')]
+    pub markers: Vec<String>,
+
+    /// マーカーの出現箇所（ファイル:行）を一覧表示（--markers 省略時は TODO,FIXME,HACK）
+    #[arg(long)]
+    pub list_markers: bool,
+
+    /// バイト数・文字数・行長の統計を表示
+    #[arg(long)]
+    pub text_stats: bool,
+
+    /// 指定したgitリビジョンから作業ツリーまでの変更行（追加・削除・変更）をコード/コメント/空行別に集計
+    #[arg(long, value_name = "REV")]
+    pub since: Option<String>,
+
+    /// COCOMOによる工数・コスト見積もりを表示
+    #[arg(long)]
+    pub estimate: bool,
+
+    /// COCOMOのモード
+    #[arg(long, value_enum, default_value_t = CocomoMode::Organic)]
+    pub cocomo_mode: CocomoMode,
+
+    /// 開発者1人あたりの年間給与
+    #[arg(long, default_value_t = DEFAULT_SALARY)]
+    pub salary: f64,
+
+    /// 給与に掛けるオーバーヘッド係数
+    #[arg(long, default_value_t = DEFAULT_OVERHEAD)]
+    pub overhead: f64,
+
+    /// .qcount.toml を読み込まない
+    #[arg(long)]
+    pub no_config: bool,
+
+    /// ベースラインファイル（qcount-baseline.json）のルールと比較し、指標が悪化していれば失敗
+    #[arg(long, value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+
+    /// 改善した指標（と未記録のルール）の値で --baseline のファイルを更新。ファイルがなければ現在のレポートから作成
+    #[arg(long, requires = "baseline")]
+    pub update_baseline: bool,
+
+    /// 集計結果をSQLiteデータベースに追記（files / languages / directories テーブル）
+    #[arg(long, value_name = "DB")]
+    pub sqlite: Option<PathBuf>,
+
+    /// --sqlite で記録する実行のラベル
+    #[arg(long, requires = "sqlite")]
+    pub label: Option<String>,
+
+    /// ディレクトリツリーを対話的に閲覧するフルスクリーンUIを起動
+    #[arg(long, conflicts_with_all = ["json", "format", "output", "baseline"])]
+    pub tui: bool,
+
+    /// JSONレポートのJSON Schemaを出力して終了
+    #[arg(long)]
+    pub json_schema: bool,
+}
+
+/// Additional report destination given with `--output FORMAT=PATH`.
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub format: OutputFormat,
+    pub path: PathBuf,
+}
+
+fn parse_output(value: &str) -> Result<Output, String> {
+    let (format, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected FORMAT=PATH, got '{}'", value))?;
+    if path.is_empty() {
+        return Err(format!("missing path for '{}'", format));
+    }
+    Ok(Output {
+        format: OutputFormat::from_str(format, true)?,
+        path: PathBuf::from(path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        let output = parse_output("JSON=out/report.json").unwrap();
+        assert_eq!(output.format, OutputFormat::Json);
+        assert_eq!(output.path, PathBuf::from("out/report.json"));
+        assert_eq!(
+            parse_output("markdown=a=b.md").unwrap().path,
+            PathBuf::from("a=b.md")
+        );
+        assert!(parse_output("report.json").is_err());
+        assert!(parse_output("json=").is_err());
+        assert!(parse_output("xml=report.xml").is_err());
+    }
+
+    #[test]
+    fn test_markers_takes_a_tag_list() {
+        let args = Args::try_parse_from(["qcount", "--markers", "TODO,FIXME,XXX", "src"]).unwrap();
+        assert_eq!(args.markers, ["TODO", "FIXME", "XXX"]);
+        assert_eq!(args.paths, [PathBuf::from("src")]);
+
+        let args = Args::try_parse_from(["qcount", "src", "--list-markers"]).unwrap();
+        assert!(args.markers.is_empty());
+        assert!(args.list_markers);
+    }
 }
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result, bail};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+
+/// Locations searched for a CODEOWNERS file, relative to a repository root
+/// (GitHub and GitLab conventions).
+const LOCATIONS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+struct Rule {
+    matcher: Gitignore,
+    owners: Vec<String>,
+}
+
+/// Parsed CODEOWNERS file. Patterns use gitignore semantics and the last
+/// matching rule wins.
+pub struct CodeOwners {
+    /// Directory the patterns are relative to
+    base: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    /// Finds the CODEOWNERS file for `start`, searching it and its ancestors.
+    pub fn discover(start: &Path) -> Result<Self> {
+        let start = std::fs::canonicalize(start)
+            .with_context(|| format!("cannot resolve {}", start.display()))?;
+        for dir in start.ancestors() {
+            for location in LOCATIONS {
+                let candidate = dir.join(location);
+                if candidate.is_file() {
+                    let content = std::fs::read_to_string(&candidate)
+                        .with_context(|| format!("cannot read {}", candidate.display()))?;
+                    return CodeOwners::parse(&content, dir);
+                }
+            }
+        }
+        bail!("no CODEOWNERS file found for {}", start.display())
+    }
+
+    pub fn parse(content: &str, base: &Path) -> Result<Self> {
+        let mut rules = Vec::new();
+        for line in content.lines() {
+            let line = line.trim();
+            // Comments, blank lines and GitLab section headers (`[Section]`, `^[Section]`)
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with('[')
+                || line.starts_with("^[")
+            {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let Some(pattern) = fields.next() else {
+                continue;
+            };
+            let owners = fields
+                .take_while(|f| !f.starts_with('#'))
+                .map(str::to_string)
+                .collect();
+
+            let mut builder = GitignoreBuilder::new(base);
+            builder
+                .add_line(None, pattern)
+                .with_context(|| format!("invalid CODEOWNERS pattern: {}", pattern))?;
+            rules.push(Rule {
+                matcher: builder.build()?,
+                owners,
+            });
+        }
+        Ok(CodeOwners {
+            base: base.to_path_buf(),
+            rules,
+        })
+    }
+
+    /// Owners of `path` (empty when unowned). `path` may be relative to the
+    /// current directory or absolute.
+    pub fn owners_of(&self, path: &Path) -> &[String] {
+        let absolute = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let Ok(relative) = absolute.strip_prefix(&self.base) else {
+            return &[];
+        };
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| {
+                r.matcher
+                    .matched_path_or_any_parents(relative, false)
+                    .is_ignore()
+            })
+            .map(|r| r.owners.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+# Default owners
+*       @acme/core
+
+*.js    @acme/web
+/docs/  @acme/docs
+apps/**/generated/
+[Backend]
+/services/api/ @acme/api @alice # inline comment
+";
+
+    fn owners(path: &str) -> Vec<String> {
+        let co = CodeOwners::parse(SAMPLE, Path::new("/repo")).unwrap();
+        co.owners_of(&Path::new("/repo").join(path)).to_vec()
+    }
+
+    #[test]
+    fn test_last_match_wins() {
+        assert_eq!(owners("src/main.rs"), vec!["@acme/core"]);
+        assert_eq!(owners("web/app.js"), vec!["@acme/web"]);
+        assert_eq!(owners("docs/guide/index.md"), vec!["@acme/docs"]);
+        assert_eq!(
+            owners("services/api/src/lib.rs"),
+            vec!["@acme/api", "@alice"]
+        );
+    }
+
+    #[test]
+    fn test_rule_without_owners_unowns() {
+        assert!(owners("apps/web/generated/schema.ts").is_empty());
+    }
+
+    #[test]
+    fn test_anchored_pattern() {
+        // `/docs/` only matches at the root
+        assert_eq!(owners("lib/docs/readme.md"), vec!["@acme/core"]);
+    }
+
+    #[test]
+    fn test_outside_base_is_unowned() {
+        assert!(owners("/elsewhere/x.rs").is_empty());
+    }
+}
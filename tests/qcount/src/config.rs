@@ -0,0 +1,158 @@
+use crate::language::{Language, language_from_name};
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Name of the project configuration file.
+pub const CONFIG_FILE: &str = ".qcount.toml";
+
+/// Output format selectable from `--format` or the config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Table,
+    Json,
+    /// Prometheus / OpenMetrics text format
+    Openmetrics,
+    /// GitHub-flavored Markdown tables
+    Markdown,
+}
+
+/// Thresholds used while counting.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Thresholds {
+    /// Same as `--line-width`
+    pub long_line_width: Option<usize>,
+}
+
+/// Contents of `.qcount.toml`. Every setting is a default that the
+/// matching command-line flag overrides.
+///
+/// ```toml
+/// exclude = ["vendor/", "*.min.js"]
+/// format = "json"
+/// by_dir = true
+///
+/// [languages]
+/// h = "cpp"
+///
+/// [thresholds]
+/// long_line_width = 100
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Added to `--exclude` patterns
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Used when no `--include` is given
+    #[serde(default)]
+    pub include: Vec<String>,
+    pub format: Option<OutputFormat>,
+    #[serde(default)]
+    pub by_dir: bool,
+    #[serde(default)]
+    pub by_file: bool,
+    /// Extension (without the dot) to language name, e.g. `h = "cpp"`
+    #[serde(default)]
+    pub languages: HashMap<String, String>,
+    #[serde(default)]
+    pub thresholds: Thresholds,
+}
+
+impl Config {
+    /// Finds the nearest `.qcount.toml` in `start` or its ancestors.
+    /// Returns the file path with the parsed config, or None when there is none.
+    pub fn discover(start: &Path) -> Result<Option<(PathBuf, Config)>> {
+        let start = std::fs::canonicalize(start)
+            .with_context(|| format!("cannot resolve {}", start.display()))?;
+        for dir in start.ancestors() {
+            let candidate = dir.join(CONFIG_FILE);
+            if candidate.is_file() {
+                let config = Config::load(&candidate)?;
+                return Ok(Some((candidate, config)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn load(path: &Path) -> Result<Config> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let config: Config =
+            toml::from_str(&content).with_context(|| format!("invalid {}", path.display()))?;
+        // Fail early on unknown language names
+        config
+            .language_overrides()
+            .with_context(|| format!("invalid {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// `[languages]` resolved to extension -> language.
+    pub fn language_overrides(&self) -> Result<HashMap<String, Language>> {
+        let mut overrides = HashMap::new();
+        for (ext, name) in &self.languages {
+            let Some(language) = language_from_name(name) else {
+                bail!("unknown language {:?} for extension {:?}", name, ext);
+            };
+            overrides.insert(ext.trim_start_matches('.').to_string(), language);
+        }
+        Ok(overrides)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full_config() {
+        let config: Config = toml::from_str(
+            r#"
+exclude = ["vendor/"]
+format = "json"
+by_dir = true
+
+[languages]
+".h" = "C++"
+tpl = "javascript"
+
+[thresholds]
+long_line_width = 100
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.exclude, vec!["vendor/"]);
+        assert_eq!(config.format, Some(OutputFormat::Json));
+        assert!(config.by_dir);
+        assert!(!config.by_file);
+        assert_eq!(config.thresholds.long_line_width, Some(100));
+
+        let overrides = config.language_overrides().unwrap();
+        assert_eq!(overrides["h"], Language::Cpp);
+        assert_eq!(overrides["tpl"], Language::JavaScript);
+    }
+
+    #[test]
+    fn test_rejects_unknown_keys_and_languages() {
+        assert!(toml::from_str::<Config>("exlude = []\n").is_err());
+
+        let config: Config = toml::from_str("[languages]\nx = \"cobol\"\n").unwrap();
+        assert!(config.language_overrides().is_err());
+    }
+
+    #[test]
+    fn test_discover_walks_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert!(Config::discover(&nested).unwrap().is_none());
+
+        std::fs::write(dir.path().join(CONFIG_FILE), "by_file = true\n").unwrap();
+        let (path, config) = Config::discover(&nested).unwrap().unwrap();
+        assert_eq!(path.file_name().unwrap(), CONFIG_FILE);
+        assert!(config.by_file);
+    }
+}
@@ -1,9 +1,146 @@
-use crate::language::{language_def, Language};
+use crate::fastcount;
+use crate::hygiene::{self, Hygiene};
+use crate::language::{Language, language_def};
+use crate::license;
+use crate::marker::{MarkerCounts, MarkerHit, Scanner};
+use crate::notebook;
+use crate::symbol::{self, Symbols};
+use crate::testfile::is_test_path;
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::Serialize;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize)]
+/// Default width above which a line counts as long.
+pub const DEFAULT_LONG_LINE_WIDTH: usize = 120;
+
+/// Characters trimmed from both ends of a line before it is classified;
+/// a line of nothing else is blank.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlankPolicy {
+    /// Unicode whitespace (U+3000, NBSP, ...) plus the BOM and zero-width
+    /// characters, which editors render as nothing
+    #[default]
+    Unicode,
+    /// `char::is_whitespace` only, as `str::trim` does (`--strict-ascii-blank`);
+    /// the BOM and zero-width characters are content
+    Whitespace,
+}
+
+impl BlankPolicy {
+    pub fn is_blank(self, c: char) -> bool {
+        match self {
+            BlankPolicy::Unicode => {
+                c.is_whitespace()
+                    || matches!(
+                        c,
+                        '\u{FEFF}' | '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{180E}'
+                    )
+            }
+            BlankPolicy::Whitespace => c.is_whitespace(),
+        }
+    }
+
+    pub fn trim(self, line: &str) -> &str {
+        line.trim_matches(|c| self.is_blank(c))
+    }
+}
+
+/// Tunables for `count` / `count_content`.
+#[derive(Debug, Clone)]
+pub struct CountOptions {
+    /// Lines with more characters than this are counted in `long_lines`
+    pub long_line_width: usize,
+    /// Only total and blank lines are needed; use the byte-level fast path
+    pub total_only: bool,
+    /// Tags counted in comment lines (empty = none)
+    pub markers: Vec<String>,
+    /// Record every marker occurrence in `FileStats::marker_hits`
+    pub list_markers: bool,
+    /// Detect the license header into `FileStats::license`
+    pub licenses: bool,
+    /// Scan line endings and whitespace into `FileStats::hygiene`
+    pub hygiene: bool,
+    /// Count declarations in code lines into `FileStats::symbols`
+    pub symbols: bool,
+    /// What counts as blank when lines are trimmed
+    pub blank: BlankPolicy,
+}
+
+impl Default for CountOptions {
+    fn default() -> Self {
+        CountOptions {
+            long_line_width: DEFAULT_LONG_LINE_WIDTH,
+            total_only: false,
+            markers: Vec::new(),
+            list_markers: false,
+            licenses: false,
+            hygiene: false,
+            symbols: false,
+            blank: BlankPolicy::Unicode,
+        }
+    }
+}
+
+/// Size and line-length statistics of a file or a group of files.
+/// Lengths are in characters and exclude the line terminator.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct TextStats {
+    pub bytes: u64,
+    pub chars: u64,
+    pub max_line_length: u64,
+    pub mean_line_length: f64,
+    /// Lines longer than the configured width
+    pub long_lines: u64,
+    #[serde(skip)]
+    line_length_sum: u64,
+    #[serde(skip)]
+    lines: u64,
+}
+
+impl TextStats {
+    pub fn new(bytes: u64, chars: u64) -> Self {
+        TextStats {
+            bytes,
+            chars,
+            ..TextStats::default()
+        }
+    }
+
+    pub fn add_line(&mut self, len: u64, long_line_width: usize) {
+        self.lines += 1;
+        self.line_length_sum += len;
+        self.max_line_length = self.max_line_length.max(len);
+        if len > long_line_width as u64 {
+            self.long_lines += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &TextStats) {
+        self.bytes += other.bytes;
+        self.chars += other.chars;
+        self.merge_lines(other);
+    }
+
+    /// Merges the line statistics only, keeping `bytes` and `chars`.
+    pub fn merge_lines(&mut self, other: &TextStats) {
+        self.max_line_length = self.max_line_length.max(other.max_line_length);
+        self.long_lines += other.long_lines;
+        self.line_length_sum += other.line_length_sum;
+        self.lines += other.lines;
+        self.update_mean();
+    }
+
+    pub fn update_mean(&mut self) {
+        self.mean_line_length = if self.lines == 0 {
+            0.0
+        } else {
+            self.line_length_sum as f64 / self.lines as f64
+        };
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct FileStats {
     pub path: PathBuf,
     pub language: Language,
@@ -11,6 +148,27 @@ pub struct FileStats {
     pub code_lines: u64,
     pub blank_lines: u64,
     pub comment_lines: u64,
+    /// Documentation lines (markdown cells of notebooks); 0 for other files
+    pub doc_lines: u64,
+    /// Code lines that belong to tests (test files or `#[cfg(test)]` items)
+    pub test_code_lines: u64,
+    /// Code lines that are not test code
+    pub production_code_lines: u64,
+    pub text: TextStats,
+    /// Marker tags found in comment lines
+    pub markers: MarkerCounts,
+    /// Marker occurrences, kept only with `CountOptions::list_markers`
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub marker_hits: Vec<MarkerHit>,
+    /// License of the file header (SPDX expression), filled with `--licenses`
+    pub license: Option<String>,
+    /// Line-ending and whitespace audit, filled with `--hygiene`
+    pub hygiene: Hygiene,
+    /// Notebook cells counted (code and markdown); 0 for other files
+    pub cells: u64,
+    /// Functions, types and modules declared, filled with `--symbols`
+    pub symbols: Symbols,
 }
 
 #[derive(Debug, PartialEq)]
@@ -19,77 +177,224 @@ enum State {
     InBlockComment,
 }
 
-pub fn count(path: &Path, language: Language) -> Result<FileStats> {
-    let content = std::fs::read_to_string(path)?;
-    let stats = count_content(&content, path, language);
-    Ok(stats)
+/// Kind of a source line as counted by `count_content`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineKind {
+    Blank,
+    Comment,
+    Code,
+    /// Documentation prose, i.e. notebook markdown; the classifier never
+    /// yields it
+    Doc,
 }
 
-pub fn count_content(content: &str, path: &Path, language: Language) -> FileStats {
-    let def = language_def(&language);
-    let line_comments: &[&str] = def.map(|d| d.line_comment).unwrap_or(&[]);
-    let block_start: Option<&str> = def.and_then(|d| d.block_comment_start);
-    let block_end: Option<&str> = def.and_then(|d| d.block_comment_end);
-
-    let mut total_lines: u64 = 0;
-    let mut code_lines: u64 = 0;
-    let mut blank_lines: u64 = 0;
-    let mut comment_lines: u64 = 0;
-    let mut state = State::Normal;
+/// Line-by-line comment state machine of one file.
+struct Classifier {
+    line_comments: &'static [&'static str],
+    block_start: Option<&'static str>,
+    block_end: Option<&'static str>,
+    state: State,
+}
 
-    for line in content.lines() {
-        total_lines += 1;
-        let trimmed = line.trim();
+impl Classifier {
+    fn new(language: &Language) -> Self {
+        let def = language_def(language);
+        Classifier {
+            line_comments: def.map(|d| d.line_comment).unwrap_or(&[]),
+            block_start: def.and_then(|d| d.block_comment_start),
+            block_end: def.and_then(|d| d.block_comment_end),
+            state: State::Normal,
+        }
+    }
 
+    /// Classifies the next line, given trimmed.
+    fn classify(&mut self, trimmed: &str) -> LineKind {
         if trimmed.is_empty() {
-            blank_lines += 1;
-            continue;
+            return LineKind::Blank;
         }
 
-        match state {
+        match self.state {
             State::InBlockComment => {
-                comment_lines += 1;
-                if let Some(end) = block_end {
-                    if trimmed.contains(end) {
-                        state = State::Normal;
-                    }
+                if let Some(end) = self.block_end
+                    && trimmed.contains(end)
+                {
+                    self.state = State::Normal;
                 }
+                LineKind::Comment
             }
             State::Normal => {
                 // Check line comment
-                let is_line_comment = line_comments.iter().any(|&lc| trimmed.starts_with(lc));
-                if is_line_comment {
-                    comment_lines += 1;
-                    continue;
+                if self.line_comments.iter().any(|&lc| trimmed.starts_with(lc)) {
+                    return LineKind::Comment;
                 }
 
                 // Check block comment start
-                if let Some(start) = block_start {
-                    if trimmed.contains(start) {
-                        comment_lines += 1;
-                        // Check if the block ends on the same line
-                        if let Some(end) = block_end {
-                            let after_start = trimmed
-                                .find(start)
-                                .map(|i| &trimmed[i + start.len()..])
-                                .unwrap_or("");
-                            if after_start.contains(end) {
-                                // Single-line block comment, stay in Normal
-                            } else {
-                                state = State::InBlockComment;
-                            }
-                        } else {
-                            state = State::InBlockComment;
-                        }
-                        continue;
+                if let Some(start) = self.block_start
+                    && trimmed.contains(start)
+                {
+                    // Check if the block ends on the same line
+                    let closed = self.block_end.is_some_and(|end| {
+                        let after_start = trimmed
+                            .find(start)
+                            .map(|i| &trimmed[i + start.len()..])
+                            .unwrap_or("");
+                        after_start.contains(end)
+                    });
+                    if !closed {
+                        self.state = State::InBlockComment;
                     }
+                    return LineKind::Comment;
                 }
 
+                LineKind::Code
+            }
+        }
+    }
+}
+
+/// Kind of every line of `content`, classified exactly as `count_content` does.
+pub fn line_kinds(content: &str, language: &Language, blank: BlankPolicy) -> Vec<LineKind> {
+    let mut classifier = Classifier::new(language);
+    content
+        .lines()
+        .map(|line| classifier.classify(blank.trim(line)))
+        .collect()
+}
+
+/// Tracks Rust `#[cfg(test)]` items by brace depth.
+#[derive(Debug, Default)]
+struct CfgTestTracker {
+    /// `#[cfg(test)]` seen, waiting for the item it applies to
+    pending: bool,
+    /// Brace depth inside the current `#[cfg(test)]` item (0 = outside)
+    depth: i64,
+}
+
+impl CfgTestTracker {
+    /// Feeds a code line and returns true if it is part of a `#[cfg(test)]` item.
+    fn code_line(&mut self, trimmed: &str) -> bool {
+        if self.depth > 0 {
+            self.depth += brace_delta(trimmed);
+            return true;
+        }
+
+        if trimmed.starts_with("#[cfg(test)]") {
+            self.pending = true;
+        }
+        if !self.pending {
+            return false;
+        }
+
+        // The attribute may share a line with the item (`#[cfg(test)] mod tests {`)
+        let item = trimmed.trim_start_matches("#[cfg(test)]").trim();
+        if item.contains('{') {
+            self.pending = false;
+            self.depth = brace_delta(item).max(0);
+        } else if item.ends_with(';') {
+            self.pending = false;
+        }
+        true
+    }
+}
+
+fn brace_delta(line: &str) -> i64 {
+    line.chars().fold(0, |d, c| match c {
+        '{' => d + 1,
+        '}' => d - 1,
+        _ => d,
+    })
+}
+
+impl FileStats {
+    /// Reclassifies every code line as test code (the whole file is a test file).
+    pub fn mark_as_test(&mut self) {
+        self.test_code_lines = self.code_lines;
+        self.production_code_lines = 0;
+    }
+}
+
+/// Counts a file. `root` is the walk root; test files are recognized by their
+/// path relative to it, so a root that itself lives under `tests/` is not
+/// treated as test code wholesale.
+///
+/// `--total-only` runs and languages without comment syntax take the
+/// byte-level fast path in `fastcount`, which finds no markers or symbols.
+pub fn count(
+    path: &Path,
+    root: &Path,
+    language: Language,
+    options: &CountOptions,
+) -> Result<FileStats> {
+    let license = if options.licenses {
+        license::detect_file(path, &language)?
+    } else {
+        None
+    };
+    let mut stats = if language == Language::Jupyter {
+        notebook::count(path, options)?
+    } else if options.total_only || fastcount::has_no_comments(&language) {
+        fastcount::count(path, language, options)?
+    } else {
+        let content = std::fs::read_to_string(path)?;
+        count_content(&content, path, language, options)
+    };
+    stats.license = license;
+    if is_test_path(path.strip_prefix(root).unwrap_or(path)) {
+        stats.mark_as_test();
+    }
+    Ok(stats)
+}
+
+pub fn count_content(
+    content: &str,
+    path: &Path,
+    language: Language,
+    options: &CountOptions,
+) -> FileStats {
+    let mut total_lines: u64 = 0;
+    let mut code_lines: u64 = 0;
+    let mut blank_lines: u64 = 0;
+    let mut comment_lines: u64 = 0;
+    let mut doc_lines: u64 = 0;
+    let mut test_code_lines: u64 = 0;
+    let mut classifier = Classifier::new(&language);
+    let mut cfg_test = CfgTestTracker::default();
+    let mut text = TextStats::new(content.len() as u64, content.chars().count() as u64);
+    let mut markers = Scanner::new(&options.markers, options.list_markers);
+    let declarations = if options.symbols {
+        symbol::matcher(&language)
+    } else {
+        None
+    };
+    let mut symbols = Symbols::default();
+
+    for line in content.lines() {
+        total_lines += 1;
+        text.add_line(line.chars().count() as u64, options.long_line_width);
+        let trimmed = options.blank.trim(line);
+
+        match classifier.classify(trimmed) {
+            LineKind::Blank => blank_lines += 1,
+            LineKind::Comment => {
+                comment_lines += 1;
+                markers.comment_line(path, total_lines, trimmed);
+            }
+            LineKind::Code => {
                 code_lines += 1;
+                if let Some(declarations) = declarations {
+                    declarations.code_line(trimmed, &mut symbols);
+                }
+                let in_cfg_test = language == Language::Rust && cfg_test.code_line(trimmed);
+                if in_cfg_test {
+                    test_code_lines += 1;
+                }
             }
+            LineKind::Doc => doc_lines += 1,
         }
     }
 
+    text.update_mean();
+
     FileStats {
         path: path.to_path_buf(),
         language,
@@ -97,6 +402,20 @@ pub fn count_content(content: &str, path: &Path, language: Language) -> FileStat
         code_lines,
         blank_lines,
         comment_lines,
+        doc_lines,
+        test_code_lines,
+        production_code_lines: code_lines - test_code_lines,
+        text,
+        markers: markers.counts,
+        marker_hits: markers.hits,
+        license: None,
+        hygiene: if options.hygiene {
+            hygiene::scan(content.as_bytes())
+        } else {
+            Hygiene::default()
+        },
+        cells: 0,
+        symbols,
     }
 }
 
@@ -106,7 +425,7 @@ mod tests {
     use std::path::Path;
 
     fn stats(content: &str, lang: Language) -> FileStats {
-        count_content(content, Path::new("test"), lang)
+        count_content(content, Path::new("test"), lang, &CountOptions::default())
     }
 
     #[test]
@@ -155,4 +474,89 @@ mod tests {
         assert_eq!(s.total_lines, 1);
         assert_eq!(s.comment_lines, 1);
     }
+
+    #[test]
+    fn test_rust_cfg_test_module() {
+        let content = "fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\n#[cfg(test)]\nmod tests {\n    // helper\n    #[test]\n    fn it_adds() {\n        assert_eq!(super::add(1, 2), 3);\n    }\n}\n\nfn after() {}\n";
+        let s = stats(content, Language::Rust);
+        assert_eq!(s.code_lines, 11);
+        assert_eq!(s.comment_lines, 1);
+        assert_eq!(s.test_code_lines, 7);
+        assert_eq!(s.production_code_lines, 4);
+    }
+
+    #[test]
+    fn test_rust_cfg_test_single_item() {
+        let content = "#[cfg(test)]\nuse std::fs;\nfn main() {}\n";
+        let s = stats(content, Language::Rust);
+        assert_eq!(s.test_code_lines, 2);
+        assert_eq!(s.production_code_lines, 1);
+    }
+
+    #[test]
+    fn test_test_file_is_all_test_code() {
+        let mut s = stats("x = 1\n# c\ny = 2\n", Language::Python);
+        assert_eq!(s.production_code_lines, 2);
+        s.mark_as_test();
+        assert_eq!(s.test_code_lines, 2);
+        assert_eq!(s.production_code_lines, 0);
+    }
+
+    #[test]
+    fn test_text_stats() {
+        let content = "ab\r\n\nあいう\n";
+        let s = stats(content, Language::Python);
+        assert_eq!(s.text.bytes, 15);
+        assert_eq!(s.text.chars, 9);
+        assert_eq!(s.text.max_line_length, 3);
+        assert!((s.text.mean_line_length - 5.0 / 3.0).abs() < 1e-9);
+        assert_eq!(s.text.long_lines, 0);
+    }
+
+    #[test]
+    fn test_unicode_blank_and_invisible_characters() {
+        let content =
+            "\u{feff}// BOM\n\u{3000}\n\u{a0}\u{a0}\n\u{200b}\n\u{3000}// 全角\nlet x = 1;\n";
+        let s = stats(content, Language::Rust);
+        assert_eq!(s.blank_lines, 3);
+        assert_eq!(s.comment_lines, 2);
+        assert_eq!(s.code_lines, 1);
+
+        let options = CountOptions {
+            blank: BlankPolicy::Whitespace,
+            ..CountOptions::default()
+        };
+        let s = count_content(content, Path::new("t"), Language::Rust, &options);
+        assert_eq!(s.blank_lines, 2);
+        assert_eq!(s.comment_lines, 1);
+        assert_eq!(s.code_lines, 3);
+    }
+
+    #[test]
+    fn test_whitespace_policy_matches_str_trim() {
+        for line in [" \t\x0b\x0c\r", "\u{3000}x\u{a0}", "\u{feff}x", "\u{200b}"] {
+            assert_eq!(BlankPolicy::Whitespace.trim(line), line.trim());
+        }
+        assert_eq!(BlankPolicy::Unicode.trim("\u{2060}x\u{200d}"), "x");
+    }
+
+    #[test]
+    fn test_long_lines_use_configured_width() {
+        let options = CountOptions {
+            long_line_width: 4,
+            ..CountOptions::default()
+        };
+        let s = count_content("abcd\nabcde\n", Path::new("t"), Language::Rust, &options);
+        assert_eq!(s.text.long_lines, 1);
+        assert_eq!(s.text.max_line_length, 5);
+    }
+
+    #[test]
+    fn test_text_stats_merge_recomputes_mean() {
+        let mut a = stats("aa\n", Language::Rust).text;
+        let b = stats("aaaa\naaaa\naaaa\n", Language::Rust).text;
+        a.merge(&b);
+        assert_eq!(a.max_line_length, 4);
+        assert!((a.mean_line_length - 3.5).abs() < 1e-9);
+    }
 }
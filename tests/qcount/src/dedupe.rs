@@ -0,0 +1,110 @@
+use crate::language::Language;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Recognizes files already counted during a walk, either because they are
+/// hardlinks to the same inode or because their contents are identical.
+///
+/// Which copy counts as the original depends on walk order; totals do not.
+#[derive(Debug, Default)]
+pub struct Dedupe {
+    inodes: Mutex<HashSet<(u64, u64)>>,
+    /// (language, length, hash) -> first file seen with that content
+    contents: Mutex<HashMap<(Language, u64, u64), PathBuf>>,
+}
+
+impl Dedupe {
+    pub fn new() -> Self {
+        Dedupe::default()
+    }
+
+    /// Records `path` and returns whether an identical file was seen before.
+    /// Files are only compared with files of the same language, so a copy
+    /// under another extension is still counted.
+    pub fn is_duplicate(&self, path: &Path, lang: Language) -> io::Result<bool> {
+        let metadata = std::fs::metadata(path)?;
+        if let Some(id) = inode(&metadata)
+            && !self.inodes.lock().unwrap().insert(id)
+        {
+            return Ok(true);
+        }
+
+        let content = std::fs::read(path)?;
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let key = (lang, content.len() as u64, hasher.finish());
+
+        let first = match self.contents.lock().unwrap().entry(key) {
+            Entry::Vacant(e) => {
+                e.insert(path.to_path_buf());
+                return Ok(false);
+            }
+            Entry::Occupied(e) => e.get().clone(),
+        };
+        // Equal hashes are confirmed byte for byte; a collision is counted
+        Ok(std::fs::read(first).is_ok_and(|c| c == content))
+    }
+}
+
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copies_are_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.rs");
+        let c = dir.path().join("c.rs");
+        std::fs::write(&a, "fn a() {}\n").unwrap();
+        std::fs::write(&b, "fn a() {}\n").unwrap();
+        std::fs::write(&c, "fn c() {}\n").unwrap();
+
+        let dedupe = Dedupe::new();
+        assert!(!dedupe.is_duplicate(&a, Language::Rust).unwrap());
+        assert!(dedupe.is_duplicate(&b, Language::Rust).unwrap());
+        assert!(!dedupe.is_duplicate(&c, Language::Rust).unwrap());
+    }
+
+    #[test]
+    fn test_same_content_in_another_language_is_not_a_duplicate() {
+        let dir = tempfile::tempdir().unwrap();
+        let js = dir.path().join("a.js");
+        let ts = dir.path().join("a.ts");
+        std::fs::write(&js, "let x = 1;\n").unwrap();
+        std::fs::write(&ts, "let x = 1;\n").unwrap();
+
+        let dedupe = Dedupe::new();
+        assert!(!dedupe.is_duplicate(&js, Language::JavaScript).unwrap());
+        assert!(!dedupe.is_duplicate(&ts, Language::TypeScript).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hardlinks_are_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.py");
+        let b = dir.path().join("b.py");
+        std::fs::write(&a, "x = 1\n").unwrap();
+        std::fs::hard_link(&a, &b).unwrap();
+
+        let dedupe = Dedupe::new();
+        assert!(!dedupe.is_duplicate(&a, Language::Python).unwrap());
+        assert!(dedupe.is_duplicate(&b, Language::Python).unwrap());
+    }
+}
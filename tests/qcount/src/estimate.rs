@@ -0,0 +1,136 @@
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Default annual salary used by `scc` (USD).
+pub const DEFAULT_SALARY: f64 = 56286.0;
+
+/// Default overhead multiplier applied to salary cost.
+pub const DEFAULT_OVERHEAD: f64 = 2.4;
+
+/// Basic COCOMO project class.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum CocomoMode {
+    /// Small teams, familiar problem
+    Organic,
+    /// Mixed experience, medium constraints
+    SemiDetached,
+    /// Tight hardware / operational constraints
+    Embedded,
+}
+
+impl CocomoMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            CocomoMode::Organic => "organic",
+            CocomoMode::SemiDetached => "semi-detached",
+            CocomoMode::Embedded => "embedded",
+        }
+    }
+
+    /// (a, b, c, d) coefficients of basic COCOMO.
+    fn coefficients(self) -> (f64, f64, f64, f64) {
+        match self {
+            CocomoMode::Organic => (2.4, 1.05, 2.5, 0.38),
+            CocomoMode::SemiDetached => (3.0, 1.12, 2.5, 0.35),
+            CocomoMode::Embedded => (3.6, 1.20, 2.5, 0.32),
+        }
+    }
+}
+
+/// Inputs of the estimation.
+#[derive(Debug, Clone)]
+pub struct EstimateParams {
+    pub mode: CocomoMode,
+    /// Annual salary per developer
+    pub salary: f64,
+    /// Multiplier for non-salary costs
+    pub overhead: f64,
+}
+
+/// Basic COCOMO estimate derived from code lines.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Estimate {
+    pub mode: CocomoMode,
+    pub salary: f64,
+    pub overhead: f64,
+    /// Code lines the estimate is based on
+    pub code_lines: u64,
+    pub effort_person_months: f64,
+    pub schedule_months: f64,
+    pub people: f64,
+    pub cost: f64,
+}
+
+/// Effort = a * KLOC^b, schedule = c * effort^d, cost = effort * monthly salary * overhead.
+pub fn estimate(code_lines: u64, params: &EstimateParams) -> Estimate {
+    let (a, b, c, d) = params.mode.coefficients();
+    let kloc = code_lines as f64 / 1000.0;
+
+    let effort = a * kloc.powf(b);
+    let schedule = if effort > 0.0 {
+        c * effort.powf(d)
+    } else {
+        0.0
+    };
+    let people = if schedule > 0.0 {
+        effort / schedule
+    } else {
+        0.0
+    };
+    let cost = effort * (params.salary / 12.0) * params.overhead;
+
+    Estimate {
+        mode: params.mode,
+        salary: params.salary,
+        overhead: params.overhead,
+        code_lines,
+        effort_person_months: effort,
+        schedule_months: schedule,
+        people,
+        cost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(mode: CocomoMode) -> EstimateParams {
+        EstimateParams {
+            mode,
+            salary: DEFAULT_SALARY,
+            overhead: DEFAULT_OVERHEAD,
+        }
+    }
+
+    #[test]
+    fn test_organic_estimate() {
+        let e = estimate(10_000, &params(CocomoMode::Organic));
+        // 2.4 * 10^1.05
+        assert!((e.effort_person_months - 26.93).abs() < 0.01);
+        assert!((e.schedule_months - 8.74).abs() < 0.01);
+        assert!((e.people - 3.08).abs() < 0.01);
+        let expected_cost = e.effort_person_months * DEFAULT_SALARY / 12.0 * DEFAULT_OVERHEAD;
+        assert!((e.cost - expected_cost).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_modes_are_ordered_by_effort() {
+        let organic = estimate(50_000, &params(CocomoMode::Organic));
+        let semi = estimate(50_000, &params(CocomoMode::SemiDetached));
+        let embedded = estimate(50_000, &params(CocomoMode::Embedded));
+        assert!(organic.effort_person_months < semi.effort_person_months);
+        assert!(semi.effort_person_months < embedded.effort_person_months);
+    }
+
+    #[test]
+    fn test_empty_codebase() {
+        let e = estimate(0, &params(CocomoMode::Embedded));
+        assert_eq!(e.effort_person_months, 0.0);
+        assert_eq!(e.schedule_months, 0.0);
+        assert_eq!(e.people, 0.0);
+        assert_eq!(e.cost, 0.0);
+    }
+}
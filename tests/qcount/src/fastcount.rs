@@ -0,0 +1,190 @@
+use crate::counter::{BlankPolicy, CountOptions, FileStats, TextStats};
+use crate::hygiene::{self, Hygiene};
+use crate::language::{Language, language_def};
+use crate::symbol::Symbols;
+use anyhow::Result;
+use memchr::memchr_iter;
+use memmap2::Mmap;
+use std::fs::File;
+use std::path::Path;
+
+/// Files at least this large are memory-mapped instead of read into a buffer.
+const MMAP_THRESHOLD: u64 = 1 << 20;
+
+/// True when the fast path gives the same answer as `count_content`:
+/// the language has no comment syntax, so every non-blank line is code.
+pub fn has_no_comments(language: &Language) -> bool {
+    language_def(language)
+        .is_none_or(|d| d.line_comment.is_empty() && d.block_comment_start.is_none())
+}
+
+/// Counts total and blank lines of a file without decoding it as UTF-8.
+/// Every non-blank line is reported as code and no line as comment.
+pub fn count(path: &Path, language: Language, options: &CountOptions) -> Result<FileStats> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let stats = if len >= MMAP_THRESHOLD {
+        // SAFETY: the map is read-only and dropped before returning; a file
+        // truncated concurrently by another process is outside our control,
+        // as with any reader.
+        let map = unsafe { Mmap::map(&file)? };
+        count_bytes(&map, path, language, options)
+    } else {
+        let bytes = std::fs::read(path)?;
+        count_bytes(&bytes, path, language, options)
+    };
+    Ok(stats)
+}
+
+/// Byte-level equivalent of `count_content` for total and blank lines.
+/// Line splitting follows `str::lines` (`\n` or `\r\n`).
+pub fn count_bytes(
+    bytes: &[u8],
+    path: &Path,
+    language: Language,
+    options: &CountOptions,
+) -> FileStats {
+    let mut total_lines: u64 = 0;
+    let mut blank_lines: u64 = 0;
+    let mut line_chars: u64 = 0;
+    let mut terminator_chars: u64 = 0;
+    let mut text = TextStats::default();
+
+    let mut start = 0;
+    for end in memchr_iter(b'\n', bytes).chain(std::iter::once(bytes.len())) {
+        if end == bytes.len() && start == end {
+            break; // no unterminated last line
+        }
+        let raw = &bytes[start..end];
+        let terminated = end < bytes.len();
+        let line = match raw.strip_suffix(b"\r") {
+            Some(stripped) if terminated => stripped,
+            _ => raw,
+        };
+        terminator_chars += (raw.len() - line.len()) as u64 + u64::from(terminated);
+        start = end + 1;
+
+        let chars = utf8_chars(line);
+        line_chars += chars;
+        total_lines += 1;
+        text.add_line(chars, options.long_line_width);
+        if is_blank(line, options.blank) {
+            blank_lines += 1;
+        }
+    }
+    text.bytes = bytes.len() as u64;
+    text.chars = line_chars + terminator_chars;
+    text.update_mean();
+
+    let code_lines = total_lines - blank_lines;
+    FileStats {
+        path: path.to_path_buf(),
+        language,
+        total_lines,
+        code_lines,
+        blank_lines,
+        comment_lines: 0,
+        doc_lines: 0,
+        test_code_lines: 0,
+        production_code_lines: code_lines,
+        text,
+        markers: Default::default(),
+        marker_hits: Vec::new(),
+        license: None,
+        hygiene: if options.hygiene {
+            hygiene::scan(bytes)
+        } else {
+            Hygiene::default()
+        },
+        cells: 0,
+        symbols: Symbols::default(),
+    }
+}
+
+/// Counts UTF-8 scalar values by skipping continuation bytes.
+fn utf8_chars(bytes: &[u8]) -> u64 {
+    if bytes.is_ascii() {
+        return bytes.len() as u64;
+    }
+    bytes.iter().filter(|&&b| (b & 0xC0) != 0x80).count() as u64
+}
+
+/// Blank per `BlankPolicy::trim`: ASCII whitespace is checked bytewise;
+/// lines with other bytes fall back to a check on the decoded line.
+fn is_blank(line: &[u8], policy: BlankPolicy) -> bool {
+    match line
+        .iter()
+        .position(|&b| !(b.is_ascii() && (b as char).is_whitespace()))
+    {
+        None => true,
+        Some(i) if line[i].is_ascii() => false,
+        Some(_) => std::str::from_utf8(line).is_ok_and(|s| policy.trim(s).is_empty()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::count_content;
+
+    fn both(content: &str, blank: BlankPolicy) -> (FileStats, FileStats) {
+        let options = CountOptions {
+            blank,
+            ..CountOptions::default()
+        };
+        let slow = count_content(content, Path::new("t"), Language::Rust, &options);
+        let fast = count_bytes(content.as_bytes(), Path::new("t"), Language::Rust, &options);
+        (slow, fast)
+    }
+
+    #[test]
+    fn test_matches_str_lines() {
+        for content in [
+            "",
+            "\n",
+            "a",
+            "a\n",
+            "a\n\n",
+            "a\r\n\r\nb",
+            "a\r",
+            "  \t\x0b\n\u{3000}\nx\n",
+            "あい\n\n  う  \n",
+            "\u{feff}\n\u{a0}\n\u{200b}\u{3000}\nx\u{2060}\n",
+        ] {
+            for blank in [BlankPolicy::Unicode, BlankPolicy::Whitespace] {
+                let (slow, fast) = both(content, blank);
+                assert_eq!(fast.total_lines, slow.total_lines, "{:?}", content);
+                assert_eq!(fast.blank_lines, slow.blank_lines, "{:?}", content);
+                assert_eq!(fast.text.bytes, slow.text.bytes, "{:?}", content);
+                assert_eq!(fast.text.chars, slow.text.chars, "{:?}", content);
+                assert_eq!(fast.text.max_line_length, slow.text.max_line_length);
+                assert_eq!(fast.text.mean_line_length, slow.text.mean_line_length);
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_blank_lines_are_code() {
+        let s = count_bytes(
+            b"// c\nx\n\n",
+            Path::new("t"),
+            Language::Rust,
+            &CountOptions::default(),
+        );
+        assert_eq!(s.code_lines, 2);
+        assert_eq!(s.comment_lines, 0);
+        assert_eq!(s.blank_lines, 1);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_counted() {
+        let s = count_bytes(
+            b"\xff\xfe\n\n",
+            Path::new("t"),
+            Language::Rust,
+            &CountOptions::default(),
+        );
+        assert_eq!(s.total_lines, 2);
+        assert_eq!(s.blank_lines, 1);
+    }
+}
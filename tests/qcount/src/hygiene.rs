@@ -0,0 +1,134 @@
+use memchr::memchr2_iter;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Line-ending and whitespace hygiene of a file or a group of files.
+/// `*_files` fields count files, the others count lines.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct Hygiene {
+    /// Files using only `\n`
+    pub lf_files: u64,
+    /// Files using only `\r\n`
+    pub crlf_files: u64,
+    /// Files using only a bare `\r`
+    pub cr_files: u64,
+    /// Files using more than one line-ending style
+    pub mixed_line_ending_files: u64,
+    /// Non-empty files whose last byte is not a line terminator
+    pub no_final_newline_files: u64,
+    /// Lines ending in a space or tab
+    pub trailing_whitespace_lines: u64,
+    pub trailing_whitespace_files: u64,
+    /// Non-blank lines indented with a tab
+    pub tab_indented_lines: u64,
+    /// Non-blank lines indented with a space
+    pub space_indented_lines: u64,
+    /// Files with both tab- and space-indented lines
+    pub mixed_indent_files: u64,
+}
+
+impl Hygiene {
+    pub fn merge(&mut self, other: &Hygiene) {
+        self.lf_files += other.lf_files;
+        self.crlf_files += other.crlf_files;
+        self.cr_files += other.cr_files;
+        self.mixed_line_ending_files += other.mixed_line_ending_files;
+        self.no_final_newline_files += other.no_final_newline_files;
+        self.trailing_whitespace_lines += other.trailing_whitespace_lines;
+        self.trailing_whitespace_files += other.trailing_whitespace_files;
+        self.tab_indented_lines += other.tab_indented_lines;
+        self.space_indented_lines += other.space_indented_lines;
+        self.mixed_indent_files += other.mixed_indent_files;
+    }
+}
+
+/// Scans the raw bytes of one file. Unlike `str::lines`, a bare `\r` is a
+/// line terminator here, so classic Mac files are recognized.
+pub fn scan(bytes: &[u8]) -> Hygiene {
+    let mut h = Hygiene::default();
+    let (mut lf, mut crlf, mut cr) = (0u64, 0u64, 0u64);
+
+    let mut start = 0;
+    let mut terminators = memchr2_iter(b'\n', b'\r', bytes).peekable();
+    loop {
+        let Some(end) = terminators.next() else {
+            if start < bytes.len() {
+                line(&mut h, &bytes[start..]);
+                h.no_final_newline_files = 1;
+            }
+            break;
+        };
+        line(&mut h, &bytes[start..end]);
+        start = end + 1;
+        if bytes[end] == b'\n' {
+            lf += 1;
+        } else if bytes.get(end + 1) == Some(&b'\n') {
+            crlf += 1;
+            terminators.next();
+            start += 1;
+        } else {
+            cr += 1;
+        }
+    }
+
+    match (lf > 0, crlf > 0, cr > 0) {
+        (false, false, false) => {}
+        (true, false, false) => h.lf_files = 1,
+        (false, true, false) => h.crlf_files = 1,
+        (false, false, true) => h.cr_files = 1,
+        _ => h.mixed_line_ending_files = 1,
+    }
+    h.trailing_whitespace_files = u64::from(h.trailing_whitespace_lines > 0);
+    h.mixed_indent_files = u64::from(h.tab_indented_lines > 0 && h.space_indented_lines > 0);
+    h
+}
+
+fn line(h: &mut Hygiene, line: &[u8]) {
+    if matches!(line.last(), Some(b' ' | b'\t')) {
+        h.trailing_whitespace_lines += 1;
+    }
+    if line.iter().all(|b| b.is_ascii_whitespace()) {
+        return;
+    }
+    match line.first() {
+        Some(b'\t') => h.tab_indented_lines += 1,
+        Some(b' ') => h.space_indented_lines += 1,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_endings() {
+        assert_eq!(scan(b"a\nb\n").lf_files, 1);
+        assert_eq!(scan(b"a\r\nb\r\n").crlf_files, 1);
+        assert_eq!(scan(b"a\rb\r").cr_files, 1);
+        assert_eq!(scan(b"a\r\nb\n").mixed_line_ending_files, 1);
+        assert_eq!(scan(b""), Hygiene::default());
+    }
+
+    #[test]
+    fn test_final_newline() {
+        assert_eq!(scan(b"a\nb").no_final_newline_files, 1);
+        assert_eq!(scan(b"a\nb\n").no_final_newline_files, 0);
+        assert_eq!(scan(b"a\r\n").no_final_newline_files, 0);
+        // A file without any terminator has no line-ending style
+        let h = scan(b"a");
+        assert_eq!(h.no_final_newline_files, 1);
+        assert_eq!(h.lf_files + h.crlf_files + h.cr_files, 0);
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let h = scan(b"fn a() {\n\tx; \n    y;\t\r\n  \n}\n");
+        assert_eq!(h.trailing_whitespace_lines, 3);
+        assert_eq!(h.trailing_whitespace_files, 1);
+        assert_eq!(h.tab_indented_lines, 1);
+        assert_eq!(h.space_indented_lines, 1);
+        assert_eq!(h.mixed_indent_files, 1);
+        assert_eq!(h.mixed_line_ending_files, 1);
+    }
+}
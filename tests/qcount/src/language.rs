@@ -1,6 +1,9 @@
+use schemars::JsonSchema;
 use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, JsonSchema)]
 pub enum Language {
     Rust,
     Go,
@@ -14,6 +17,14 @@ pub enum Language {
     Shell,
     Yaml,
     Toml,
+    Jupyter,
+}
+
+impl Language {
+    /// Human-readable display name (e.g. "C++" for `Cpp`)
+    pub fn name(&self) -> &'static str {
+        language_def(self).map(|d| d.name).unwrap_or("Unknown")
+    }
 }
 
 pub struct LanguageDef {
@@ -22,8 +33,39 @@ pub struct LanguageDef {
     pub line_comment: &'static [&'static str],
     pub block_comment_start: Option<&'static str>,
     pub block_comment_end: Option<&'static str>,
+    pub symbols: SymbolPatterns,
 }
 
+/// Declaration patterns counted by `--symbols`. Each entry is a regex matched
+/// at the start of a trimmed code line; a line counts at most once per kind.
+pub struct SymbolPatterns {
+    /// Functions and methods
+    pub functions: &'static [&'static str],
+    /// Structs, classes, enums, interfaces and traits
+    pub types: &'static [&'static str],
+    /// Modules, packages and namespaces declared in source
+    pub modules: &'static [&'static str],
+}
+
+impl SymbolPatterns {
+    pub const NONE: SymbolPatterns = SymbolPatterns {
+        functions: &[],
+        types: &[],
+        modules: &[],
+    };
+}
+
+/// C-style `type name(` at the start of a definition; calls have no type
+/// before the name and prototypes end with `;`
+const C_FUNCTION: &str = r"(?:[\w*&:<>,]+\s+)+[*&]*[\w:~]+\s*\([^;]*$";
+/// Shorthand methods in JS/TS class bodies: `name(args) {`
+const JS_METHOD: &str = r"(?:(?:static|async|get|set|public|private|protected)\s+)*[\w$]+\s*\([^()]*\)\s*(?::[^{]+)?\{$";
+const JS_FUNCTIONS: &[&str] = &[
+    r"(?:export\s+(?:default\s+)?)?(?:async\s+)?function\b",
+    r"(?:export\s+)?(?:const|let|var)\s+[\w$]+\s*=\s*(?:async\s+)?(?:function\b|(?:\([^()]*\)|[\w$]+)\s*=>)",
+    JS_METHOD,
+];
+
 pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
     (
         Language::Rust,
@@ -33,6 +75,15 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["//"],
             block_comment_start: Some("/*"),
             block_comment_end: Some("*/"),
+            symbols: SymbolPatterns {
+                functions: &[
+                    r#"(?:pub(?:\([^)]*\))?\s+)?(?:(?:const|async|unsafe|extern(?:\s+"[^"]*")?)\s+)*fn\s+\w"#,
+                ],
+                types: &[
+                    r"(?:pub(?:\([^)]*\))?\s+)?(?:unsafe\s+)?(?:struct|enum|union|trait)\s+\w",
+                ],
+                modules: &[r"(?:pub(?:\([^)]*\))?\s+)?mod\s+\w"],
+            },
         },
     ),
     (
@@ -43,6 +94,11 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["//"],
             block_comment_start: Some("/*"),
             block_comment_end: Some("*/"),
+            symbols: SymbolPatterns {
+                functions: &[r"func\s+(?:\([^)]*\)\s*)?\w"],
+                types: &[r"type\s+\w+(?:\[[^\]]*\])?\s+(?:struct|interface)\b"],
+                modules: &[r"package\s+\w"],
+            },
         },
     ),
     (
@@ -53,6 +109,11 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["#"],
             block_comment_start: None,
             block_comment_end: None,
+            symbols: SymbolPatterns {
+                functions: &[r"(?:async\s+)?def\s+\w"],
+                types: &[r"class\s+\w"],
+                modules: &[],
+            },
         },
     ),
     (
@@ -63,6 +124,11 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["//"],
             block_comment_start: Some("/*"),
             block_comment_end: Some("*/"),
+            symbols: SymbolPatterns {
+                functions: JS_FUNCTIONS,
+                types: &[r"(?:export\s+(?:default\s+)?)?class\b"],
+                modules: &[],
+            },
         },
     ),
     (
@@ -73,6 +139,14 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["//"],
             block_comment_start: Some("/*"),
             block_comment_end: Some("*/"),
+            symbols: SymbolPatterns {
+                functions: JS_FUNCTIONS,
+                types: &[
+                    r"(?:export\s+(?:default\s+)?)?(?:declare\s+)?(?:abstract\s+)?(?:class|interface)\s+\w",
+                    r"(?:export\s+)?(?:declare\s+)?(?:const\s+)?enum\s+\w",
+                ],
+                modules: &[r"(?:export\s+)?(?:declare\s+)?(?:namespace|module)\s+[\w.]+\s*\{"],
+            },
         },
     ),
     (
@@ -83,6 +157,13 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["//"],
             block_comment_start: Some("/*"),
             block_comment_end: Some("*/"),
+            symbols: SymbolPatterns {
+                functions: &[r"(?:[\w<>\[\],.?]+\s+)+\w+\s*\([^;]*$"],
+                types: &[
+                    r"(?:(?:public|protected|private|abstract|static|final|sealed|non-sealed|strictfp)\s+)*(?:class|interface|enum|record|@interface)\s+\w",
+                ],
+                modules: &[r"package\s+[\w.]+\s*;"],
+            },
         },
     ),
     (
@@ -93,6 +174,11 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["//"],
             block_comment_start: Some("/*"),
             block_comment_end: Some("*/"),
+            symbols: SymbolPatterns {
+                functions: &[C_FUNCTION],
+                types: &[r"(?:typedef\s+)?(?:struct|union|enum)(?:\s+\w+)?\s*\{"],
+                modules: &[],
+            },
         },
     ),
     (
@@ -103,6 +189,13 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["//"],
             block_comment_start: Some("/*"),
             block_comment_end: Some("*/"),
+            symbols: SymbolPatterns {
+                functions: &[C_FUNCTION],
+                types: &[
+                    r"(?:template\s*<.*>\s*)?(?:class|struct|union|enum(?:\s+class)?)\s+\w[^;]*$",
+                ],
+                modules: &[r"(?:inline\s+)?namespace(?:\s+[\w:]+)?\s*\{?$"],
+            },
         },
     ),
     (
@@ -113,6 +206,11 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["#"],
             block_comment_start: None,
             block_comment_end: None,
+            symbols: SymbolPatterns {
+                functions: &[r"def\s+\S"],
+                types: &[r"class\s+[A-Z]"],
+                modules: &[r"module\s+[A-Z]"],
+            },
         },
     ),
     (
@@ -123,6 +221,11 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["#"],
             block_comment_start: None,
             block_comment_end: None,
+            symbols: SymbolPatterns {
+                functions: &[r"function\s+[\w-]+", r"[\w-]+\s*\(\s*\)"],
+                types: &[],
+                modules: &[],
+            },
         },
     ),
     (
@@ -133,6 +236,7 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["#"],
             block_comment_start: None,
             block_comment_end: None,
+            symbols: SymbolPatterns::NONE,
         },
     ),
     (
@@ -143,6 +247,19 @@ pub static LANGUAGE_DEFS: &[(Language, LanguageDef)] = &[
             line_comment: &["#"],
             block_comment_start: None,
             block_comment_end: None,
+            symbols: SymbolPatterns::NONE,
+        },
+    ),
+    (
+        Language::Jupyter,
+        LanguageDef {
+            name: "Jupyter Notebook",
+            extensions: &["ipynb"],
+            // Cells are classified with the kernel language (see `notebook`)
+            line_comment: &[],
+            block_comment_start: None,
+            block_comment_end: None,
+            symbols: SymbolPatterns::NONE,
         },
     ),
 ];
@@ -156,6 +273,27 @@ pub fn language_from_extension(ext: &str) -> Option<&'static Language> {
     None
 }
 
+/// Language of a file by extension; `overrides` (extension -> language)
+/// take precedence over the built-in mappings.
+pub fn language_of(path: &Path, overrides: &HashMap<String, Language>) -> Option<Language> {
+    let ext = path.extension()?.to_str()?;
+    overrides
+        .get(ext)
+        .or_else(|| language_from_extension(ext))
+        .cloned()
+}
+
+/// Looks up a language by display name or variant name, ignoring case
+/// (e.g. "C++", "cpp", "rust").
+pub fn language_from_name(name: &str) -> Option<Language> {
+    LANGUAGE_DEFS
+        .iter()
+        .find(|(lang, def)| {
+            def.name.eq_ignore_ascii_case(name) || format!("{:?}", lang).eq_ignore_ascii_case(name)
+        })
+        .map(|(lang, _)| lang.clone())
+}
+
 pub fn language_def(lang: &Language) -> Option<&'static LanguageDef> {
     for (l, def) in LANGUAGE_DEFS {
         if l == lang {
@@ -183,6 +321,15 @@ mod tests {
         assert_eq!(language_from_extension("sh"), Some(&Language::Shell));
         assert_eq!(language_from_extension("yaml"), Some(&Language::Yaml));
         assert_eq!(language_from_extension("toml"), Some(&Language::Toml));
+        assert_eq!(language_from_extension("ipynb"), Some(&Language::Jupyter));
         assert_eq!(language_from_extension("unknown"), None);
     }
+
+    #[test]
+    fn test_name_lookup() {
+        assert_eq!(language_from_name("C++"), Some(Language::Cpp));
+        assert_eq!(language_from_name("cpp"), Some(Language::Cpp));
+        assert_eq!(language_from_name("RUST"), Some(Language::Rust));
+        assert_eq!(language_from_name("cobol"), None);
+    }
 }
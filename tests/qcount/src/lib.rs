@@ -0,0 +1,24 @@
+pub mod aggregator;
+pub mod baseline;
+pub mod churn;
+pub mod cli;
+pub mod codeowners;
+pub mod config;
+pub mod counter;
+pub mod dedupe;
+pub mod estimate;
+pub mod fastcount;
+pub mod hygiene;
+pub mod language;
+pub mod license;
+pub mod marker;
+pub mod notebook;
+pub mod package;
+pub mod pipeline;
+pub mod reporter;
+pub mod sqlite;
+pub mod symbol;
+pub mod testfile;
+pub mod tree;
+pub mod tui;
+pub mod walker;
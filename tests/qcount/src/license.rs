@@ -0,0 +1,214 @@
+use crate::counter::BlankPolicy;
+use crate::language::{Language, language_def};
+use anyhow::Result;
+use std::io::Read;
+use std::path::Path;
+
+/// Only the start of a file is read when looking for a header.
+const HEADER_BYTES: u64 = 16 * 1024;
+
+/// Lines scanned at most, comment or not.
+const HEADER_LINES: usize = 60;
+
+const SPDX_TAG: &str = "SPDX-License-Identifier:";
+
+/// Reported for a GNU license notice that does not state a version, which
+/// no SPDX identifier covers.
+pub const UNKNOWN_GNU: &str = "LicenseRef-unknown-GPL-family";
+
+/// Phrases of well-known license notices and the SPDX identifier they map to
+/// (GNU and BSD families are completed by `refine`). Checked in order against
+/// the whitespace-normalized header text.
+const KNOWN_TEXTS: &[(&str, &str)] = &[
+    ("Apache License, Version 2.0", "Apache-2.0"),
+    ("Permission is hereby granted, free of charge", "MIT"),
+    ("Mozilla Public License, v. 2.0", "MPL-2.0"),
+    ("GNU Lesser General Public License", "LGPL"),
+    ("GNU Affero General Public License", "AGPL"),
+    ("GNU General Public License", "GPL"),
+    ("Redistribution and use in source and binary forms", "BSD"),
+    (
+        "This is free and unencumbered software released into the public domain",
+        "Unlicense",
+    ),
+];
+
+/// Reads the start of `path` and detects its license header.
+pub fn detect_file(path: &Path, language: &Language) -> Result<Option<String>> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)?
+        .take(HEADER_BYTES)
+        .read_to_end(&mut head)?;
+    Ok(detect(&String::from_utf8_lossy(&head), language))
+}
+
+/// License of the leading comment block of `content`: the
+/// `SPDX-License-Identifier` expression when present, otherwise the
+/// identifier of a known license notice.
+pub fn detect(content: &str, language: &Language) -> Option<String> {
+    let header = leading_comments(content, language);
+
+    if let Some(expr) = header.iter().find_map(|line| spdx_expression(line)) {
+        return Some(expr);
+    }
+
+    let text = normalize(&header);
+    let (_, id) = KNOWN_TEXTS
+        .iter()
+        .find(|(needle, _)| text.contains(needle))?;
+    Some(refine(id, &text))
+}
+
+/// Lines of the comment block a file starts with (after an optional shebang
+/// and blank lines).
+fn leading_comments<'a>(content: &'a str, language: &Language) -> Vec<&'a str> {
+    let def = language_def(language);
+    let line_comments: &[&str] = def.map(|d| d.line_comment).unwrap_or(&[]);
+    let block_start = def.and_then(|d| d.block_comment_start);
+    let block_end = def.and_then(|d| d.block_comment_end);
+
+    let mut header = Vec::new();
+    let mut in_block = false;
+    for (i, line) in content.lines().take(HEADER_LINES).enumerate() {
+        // A BOM or zero-width character must not hide the header
+        let trimmed = BlankPolicy::Unicode.trim(line);
+        if in_block {
+            header.push(trimmed);
+            in_block = !block_end.is_some_and(|end| trimmed.contains(end));
+        } else if trimmed.is_empty() || (i == 0 && trimmed.starts_with("#!")) {
+            continue;
+        } else if line_comments.iter().any(|lc| trimmed.starts_with(lc)) {
+            header.push(trimmed);
+        } else if let Some(start) = block_start
+            && trimmed.starts_with(start)
+        {
+            header.push(trimmed);
+            let rest = &trimmed[start.len()..];
+            in_block = !block_end.is_some_and(|end| rest.contains(end));
+        } else {
+            break;
+        }
+    }
+    header
+}
+
+fn spdx_expression(line: &str) -> Option<String> {
+    let start = line.find(SPDX_TAG)? + SPDX_TAG.len();
+    let expr = line[start..]
+        .trim()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim();
+    (!expr.is_empty()).then(|| expr.to_string())
+}
+
+/// Header text without comment leaders, whitespace collapsed to single spaces.
+fn normalize(header: &[&str]) -> String {
+    header
+        .iter()
+        .flat_map(|line| line.split_whitespace())
+        .filter(|word| !word.chars().all(|c| "/*#-!".contains(c)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Completes a family to a valid SPDX identifier from the version and
+/// variant the notice states.
+fn refine(id: &str, text: &str) -> String {
+    match id {
+        "GPL" | "LGPL" | "AGPL" => {
+            let versions: &[&str] = match id {
+                "GPL" => &["3", "2", "1"],
+                "LGPL" => &["3", "2.1", "2"],
+                _ => &["3"],
+            };
+            let lower = text.to_lowercase();
+            let Some(version) = versions
+                .iter()
+                .find(|v| lower.contains(&format!("version {}", v)))
+            else {
+                return UNKNOWN_GNU.to_string();
+            };
+            let version = if version.contains('.') {
+                version.to_string()
+            } else {
+                format!("{}.0", version)
+            };
+            let variant = if lower.contains("any later version") {
+                "or-later"
+            } else {
+                "only"
+            };
+            format!("{}-{}-{}", id, version, variant)
+        }
+        "BSD" if text.contains("Neither the name") => "BSD-3-Clause".to_string(),
+        "BSD" => "BSD-2-Clause".to_string(),
+        _ => id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spdx_identifier() {
+        let rs = "// SPDX-License-Identifier: MIT OR Apache-2.0\n\nfn main() {}\n";
+        assert_eq!(
+            detect(rs, &Language::Rust),
+            Some("MIT OR Apache-2.0".into())
+        );
+
+        let c = "/* SPDX-License-Identifier: GPL-2.0-only */\n#include <stdio.h>\n";
+        assert_eq!(detect(c, &Language::C), Some("GPL-2.0-only".into()));
+
+        let py = "#!/usr/bin/env python3\n# SPDX-License-Identifier: BSD-3-Clause\n";
+        assert_eq!(detect(py, &Language::Python), Some("BSD-3-Clause".into()));
+    }
+
+    #[test]
+    fn test_known_license_texts() {
+        let apache = "\
+/*
+ * Copyright 2024 Acme
+ *
+ * Licensed under the Apache License,
+ * Version 2.0 (the \"License\");
+ */
+package main
+";
+        assert_eq!(detect(apache, &Language::Go), Some("Apache-2.0".into()));
+
+        let gpl = "\
+# This program is free software: you can redistribute it under the terms of
+# the GNU General Public License as published by the Free Software Foundation,
+# either version 3 of the License, or (at your option) any later version.
+";
+        assert_eq!(
+            detect(gpl, &Language::Shell),
+            Some("GPL-3.0-or-later".into())
+        );
+    }
+
+    #[test]
+    fn test_gnu_notices_map_to_valid_ids() {
+        let lgpl = "// GNU Lesser General Public License\n// Version 2.1, February 1999\n";
+        assert_eq!(detect(lgpl, &Language::Rust), Some("LGPL-2.1-only".into()));
+
+        let agpl = "// under the GNU Affero General Public License, version 3\n";
+        assert_eq!(detect(agpl, &Language::Rust), Some("AGPL-3.0-only".into()));
+
+        let unversioned = "// Distributed under the GNU General Public License.\n";
+        assert_eq!(
+            detect(unversioned, &Language::Rust),
+            Some(UNKNOWN_GNU.into())
+        );
+    }
+
+    #[test]
+    fn test_only_leading_comments_are_scanned() {
+        let late = "fn main() {}\n// SPDX-License-Identifier: MIT\n";
+        assert_eq!(detect(late, &Language::Rust), None);
+        assert_eq!(detect("// just a comment\nx\n", &Language::Rust), None);
+    }
+}
@@ -1,49 +1,238 @@
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use clap::Parser;
-use rayon::prelude::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::path::PathBuf;
+use std::time::{Instant, SystemTime};
 
-mod aggregator;
-mod cli;
-mod counter;
-mod language;
-mod reporter;
-mod walker;
-
-use reporter::Reporter;
+use qcount::config::{Config, OutputFormat};
+use qcount::{
+    aggregator, baseline, churn, cli, codeowners, counter, estimate, marker, package, pipeline,
+    reporter, sqlite, tree, tui, walker,
+};
 
 fn main() -> Result<()> {
     let args = cli::Args::parse();
 
-    let root = args.path.clone().unwrap_or_else(|| PathBuf::from("."));
-
-    // Walk files
-    let entries = walker::walk(&root, &args.exclude);
-
-    // Count in parallel
-    let stats: Vec<counter::FileStats> = entries
-        .par_iter()
-        .filter_map(|entry| {
-            let path = entry.path();
-            let ext = path.extension()?.to_str()?;
-            let lang = language::language_from_extension(ext)?;
-            match counter::count(path, lang.clone()) {
-                Ok(s) => Some(s),
-                Err(_) => None,
-            }
-        })
-        .collect();
+    if args.json_schema {
+        println!("{}", reporter::json::schema()?);
+        return Ok(());
+    }
 
-    // Aggregate
-    let report = aggregator::aggregate(stats, args.by_dir);
+    let started_at = SystemTime::now();
+    let started = Instant::now();
+    let roots = if args.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        args.paths.clone()
+    };
+    // A missing root would otherwise walk as an empty tree and report 0 files
+    for root in &roots {
+        std::fs::metadata(root).with_context(|| format!("cannot read {}", root.display()))?;
+    }
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    // Settings: command-line flags override `.qcount.toml`
+    let discovered = if args.no_config {
+        None
+    } else {
+        Config::discover(&roots[0])?
+    };
+    let (config_path, config) = match discovered {
+        Some((path, config)) => (Some(path), config),
+        None => (None, Config::default()),
+    };
+    let mut excludes = config.exclude.clone();
+    excludes.extend(args.exclude.iter().cloned());
+    let includes = if args.include.is_empty() {
+        config.include.clone()
+    } else {
+        args.include.clone()
+    };
+    let by_dir = args.by_dir || (config.by_dir && !args.no_by_dir);
+    let by_file = args.by_file || (config.by_file && !args.no_by_file);
+    let line_width = args
+        .line_width
+        .or(config.thresholds.long_line_width)
+        .unwrap_or(counter::DEFAULT_LONG_LINE_WIDTH);
+    let markers: Vec<String> = if args.markers.is_empty() && args.list_markers {
+        marker::DEFAULT_MARKERS
+            .iter()
+            .map(|t| t.to_string())
+            .collect()
+    } else {
+        args.markers.clone()
+    };
+    let format = if args.json {
+        OutputFormat::Json
+    } else {
+        args.format.or(config.format).unwrap_or(OutputFormat::Table)
+    };
 
-    // Report
-    if args.json {
-        let rep = reporter::json::JsonReporter;
-        rep.report(&report)?;
+    let blank = if args.strict_ascii_blank {
+        counter::BlankPolicy::Whitespace
     } else {
-        let rep = reporter::table::TableReporter;
-        rep.report(&report)?;
+        counter::BlankPolicy::Unicode
+    };
+
+    let language_overrides = config.language_overrides()?;
+    let walk = walker::WalkOptions {
+        hidden: args.hidden,
+        no_ignore: args.no_ignore,
+        follow_links: args.follow,
+        same_file_system: args.one_file_system,
+        max_depth: args.max_depth,
+        includes: args.include.clone(),
+        excludes: args.exclude.clone(),
+        // The config's globs stay relative to the directory holding it
+        config: config_path.as_ref().map(|path| walker::ConfigGlobs {
+            dir: path.parent().unwrap_or(path).to_path_buf(),
+            includes: if args.include.is_empty() {
+                config.include.clone()
+            } else {
+                Vec::new()
+            },
+            excludes: config.exclude.clone(),
+        }),
+    };
+
+    // Walk and count in one parallel pass
+    let collected = pipeline::run(&pipeline::PipelineOptions {
+        roots: roots.clone(),
+        walk: walk.clone(),
+        count: counter::CountOptions {
+            long_line_width: line_width,
+            total_only: args.total_only,
+            markers: markers.clone(),
+            list_markers: args.list_markers,
+            licenses: args.licenses,
+            hygiene: args.hygiene,
+            symbols: args.symbols,
+            blank,
+        },
+        language_overrides: language_overrides.clone(),
+        by_dir,
+        retain_files: by_file
+            || args.by_package
+            || args.by_owner
+            || args.licenses
+            || args.sqlite.is_some()
+            || args.baseline.is_some()
+            || args.tui,
+        collect_manifests: args.by_package,
+        dedupe: args.dedupe,
+        threads,
+    })?;
+
+    // Aggregate
+    let meta = aggregator::Meta {
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        roots: roots.clone(),
+        timestamp: humantime::format_rfc3339_seconds(started_at).to_string(),
+        elapsed_ms: 0,
+        threads,
+        config: config_path,
+        includes,
+        excludes,
+        markers,
+        hygiene: args.hygiene,
+        symbols: args.symbols,
+        long_line_width: line_width,
+        total_only: args.total_only,
+        strict_ascii_blank: args.strict_ascii_blank,
+        skipped_files: collected.skipped,
+    };
+    let by_package = if args.by_package {
+        let mut index =
+            package::PackageIndex::discover(collected.manifests.iter().map(|p| p.as_path()));
+        for root in &roots {
+            index.add_enclosing(root);
+        }
+        aggregator::aggregate_by_package(&collected.files, &index)
+    } else {
+        Vec::new()
+    };
+    let (by_owner, unowned_files) = if args.by_owner {
+        let owners = codeowners::CodeOwners::discover(&roots[0])?;
+        aggregator::aggregate_by_owner(&collected.files, &owners)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let (by_license, unlicensed_files) = if args.licenses {
+        aggregator::aggregate_by_license(&collected.files)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+    let mut report = collected.summary.finish(meta);
+    report.by_package = by_package;
+    report.by_owner = by_owner;
+    report.unowned_files = unowned_files;
+    report.by_license = by_license;
+    report.unlicensed_files = unlicensed_files;
+    if args.dedupe {
+        report.duplicates = Some(collected.duplicates);
+    }
+    let mut files = collected.files;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    if let Some(db) = &args.sqlite {
+        sqlite::export(db, &report, &files, args.label.as_deref())?;
+    }
+    if args.tui {
+        return tui::run(tree::DirTree::build(&roots, &files));
+    }
+    if let Some(path) = &args.baseline {
+        let mut baseline = if args.update_baseline && !path.exists() {
+            baseline::Baseline::from_report(&report)
+        } else {
+            baseline::Baseline::load(path)?
+        };
+        let mut check = baseline.check(path, &roots, &files);
+        if args.update_baseline && baseline.tighten(&check) {
+            baseline.save(path)?;
+            check.updated = true;
+        }
+        report.baseline = Some(check);
+    }
+    if by_file {
+        report.by_file = files;
+    }
+    if let Some(base) = &args.since {
+        report.churn = Some(churn::since(
+            base,
+            &roots,
+            &walk,
+            &language_overrides,
+            blank,
+        )?);
+    }
+    if args.estimate {
+        let params = estimate::EstimateParams {
+            mode: args.cocomo_mode,
+            salary: args.salary,
+            overhead: args.overhead,
+        };
+        report.estimate = Some(estimate::estimate(report.total.code_lines, &params));
+    }
+    report.meta.elapsed_ms = started.elapsed().as_millis() as u64;
+
+    // Report: stdout in the selected format, then every --output file
+    reporter::for_format(format, args.text_stats).report(&report, &mut io::stdout().lock())?;
+    for output in &args.output {
+        let file = File::create(&output.path)
+            .with_context(|| format!("cannot create {}", output.path.display()))?;
+        let mut file = BufWriter::new(file);
+        reporter::for_format(output.format, args.text_stats).report(&report, &mut file)?;
+        file.flush()?;
+    }
+
+    if let Some(check) = &report.baseline
+        && check.regressed > 0
+    {
+        bail!(
+            "{} baseline rule(s) regressed against {}",
+            check.regressed,
+            check.file.display()
+        );
     }
 
     Ok(())
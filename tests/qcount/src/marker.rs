@@ -0,0 +1,108 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Tags searched when `--list-markers` is given without `--markers`.
+pub const DEFAULT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+/// Occurrences per marker tag.
+pub type MarkerCounts = BTreeMap<String, u64>;
+
+pub fn merge_counts(into: &mut MarkerCounts, from: &MarkerCounts) {
+    for (tag, n) in from {
+        *into.entry(tag.clone()).or_default() += n;
+    }
+}
+
+/// One marker found in a comment line.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct MarkerHit {
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line: u64,
+    pub tag: String,
+    /// The comment line, trimmed
+    pub text: String,
+}
+
+/// Collects markers from the comment lines of one file.
+pub struct Scanner<'a> {
+    tags: &'a [String],
+    /// Keep every occurrence, not just the counts
+    list: bool,
+    pub counts: MarkerCounts,
+    pub hits: Vec<MarkerHit>,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(tags: &'a [String], list: bool) -> Self {
+        Scanner {
+            tags,
+            list,
+            counts: MarkerCounts::new(),
+            hits: Vec::new(),
+        }
+    }
+
+    /// Feeds a line the counter classified as comment.
+    pub fn comment_line(&mut self, path: &Path, line_no: u64, text: &str) {
+        for tag in self.tags {
+            let n = occurrences(text, tag);
+            if n == 0 {
+                continue;
+            }
+            *self.counts.entry(tag.clone()).or_default() += n;
+            if self.list {
+                self.hits.push(MarkerHit {
+                    path: path.to_path_buf(),
+                    line: line_no,
+                    tag: tag.clone(),
+                    text: text.to_string(),
+                });
+            }
+        }
+    }
+}
+
+/// Occurrences of `tag` in `text` as a whole word (`TODO:` and `TODO(bob)`
+/// match, `TODOS` and `MYTODO` do not).
+fn occurrences(text: &str, tag: &str) -> u64 {
+    if tag.is_empty() {
+        return 0;
+    }
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(tag)
+        .filter(|&(i, _)| {
+            let before = text[..i].chars().next_back();
+            let after = text[i + tag.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+        .count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whole_word_matches() {
+        assert_eq!(occurrences("// TODO: fix", "TODO"), 1);
+        assert_eq!(occurrences("// TODO(bob) and TODO", "TODO"), 2);
+        assert_eq!(occurrences("// TODOS MYTODO todo", "TODO"), 0);
+        assert_eq!(occurrences("# 未対応TODO", "TODO"), 0);
+        assert_eq!(occurrences("# 未対応 TODO", "TODO"), 1);
+    }
+
+    #[test]
+    fn test_scanner_counts_and_lists() {
+        let tags = vec!["TODO".to_string(), "FIXME".to_string()];
+        let mut scanner = Scanner::new(&tags, true);
+        scanner.comment_line(Path::new("a.rs"), 3, "// TODO: x FIXME");
+        scanner.comment_line(Path::new("a.rs"), 7, "// TODO: y");
+        assert_eq!(scanner.counts["TODO"], 2);
+        assert_eq!(scanner.counts["FIXME"], 1);
+        assert_eq!(scanner.hits.len(), 3);
+        assert_eq!(scanner.hits[2].line, 7);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::counter::{
+    BlankPolicy, CountOptions, FileStats, LineKind, TextStats, count_content, line_kinds,
+};
+use crate::hygiene::{self, Hygiene};
+use crate::language::{Language, language_from_name};
+use crate::marker::merge_counts;
+use crate::symbol::Symbols;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct Notebook {
+    #[serde(default)]
+    metadata: Metadata,
+    /// nbformat 4; older notebooks keep cells under `worksheets` and count as empty
+    #[serde(default)]
+    cells: Vec<Cell>,
+}
+
+#[derive(Default, Deserialize)]
+struct Metadata {
+    kernelspec: Option<KernelSpec>,
+    language_info: Option<LanguageInfo>,
+}
+
+#[derive(Deserialize)]
+struct KernelSpec {
+    language: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LanguageInfo {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Cell {
+    cell_type: String,
+    #[serde(default)]
+    source: Source,
+}
+
+/// Cell source: a string or a list of lines (each keeping its `\n`).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Source {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Source::Text(String::new())
+    }
+}
+
+impl Source {
+    fn text(&self) -> String {
+        match self {
+            Source::Text(text) => text.clone(),
+            Source::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+pub fn count(path: &Path, options: &CountOptions) -> Result<FileStats> {
+    let content = std::fs::read_to_string(path)?;
+    count_notebook(&content, path, options)
+}
+
+/// Counts a notebook as `Language::Jupyter`. Code cells are classified with
+/// the comment syntax of the kernel language; markdown lines count as
+/// documentation lines; raw cells and outputs are ignored. Line lengths and
+/// marker line numbers refer to the concatenated code and markdown sources.
+pub fn count_notebook(content: &str, path: &Path, options: &CountOptions) -> Result<FileStats> {
+    let notebook: Notebook = serde_json::from_str(content)
+        .with_context(|| format!("invalid notebook {}", path.display()))?;
+    let kernel = kernel_language(&notebook.metadata);
+
+    let mut stats = FileStats {
+        path: path.to_path_buf(),
+        language: Language::Jupyter,
+        total_lines: 0,
+        code_lines: 0,
+        blank_lines: 0,
+        comment_lines: 0,
+        doc_lines: 0,
+        test_code_lines: 0,
+        production_code_lines: 0,
+        text: TextStats::new(content.len() as u64, content.chars().count() as u64),
+        markers: Default::default(),
+        marker_hits: Vec::new(),
+        license: None,
+        hygiene: if options.hygiene {
+            hygiene::scan(content.as_bytes())
+        } else {
+            Hygiene::default()
+        },
+        cells: 0,
+        symbols: Symbols::default(),
+    };
+
+    for cell in &notebook.cells {
+        let source = cell.source.text();
+        match cell.cell_type.as_str() {
+            "code" => {
+                let mut cell_stats = count_content(&source, path, kernel.clone(), options);
+                for hit in &mut cell_stats.marker_hits {
+                    hit.line += stats.total_lines;
+                }
+                stats.total_lines += cell_stats.total_lines;
+                stats.code_lines += cell_stats.code_lines;
+                stats.blank_lines += cell_stats.blank_lines;
+                stats.comment_lines += cell_stats.comment_lines;
+                stats.doc_lines += cell_stats.doc_lines;
+                stats.test_code_lines += cell_stats.test_code_lines;
+                stats.production_code_lines += cell_stats.production_code_lines;
+                stats.text.merge_lines(&cell_stats.text);
+                merge_counts(&mut stats.markers, &cell_stats.markers);
+                stats.marker_hits.extend(cell_stats.marker_hits);
+                stats.symbols.merge(&cell_stats.symbols);
+            }
+            "markdown" => {
+                for line in source.lines() {
+                    stats.total_lines += 1;
+                    stats
+                        .text
+                        .add_line(line.chars().count() as u64, options.long_line_width);
+                    if options.blank.trim(line).is_empty() {
+                        stats.blank_lines += 1;
+                    } else {
+                        stats.doc_lines += 1;
+                    }
+                }
+            }
+            _ => continue,
+        }
+        stats.cells += 1;
+    }
+    stats.text.update_mean();
+    Ok(stats)
+}
+
+/// Code and markdown source lines with their kinds, in the order
+/// `count_notebook` counts them. Raw cells and outputs are left out.
+pub fn source_lines(content: &str, blank: BlankPolicy) -> Result<Vec<(String, LineKind)>> {
+    let notebook: Notebook = serde_json::from_str(content).context("invalid notebook")?;
+    let kernel = kernel_language(&notebook.metadata);
+    let mut lines = Vec::new();
+    for cell in &notebook.cells {
+        let source = cell.source.text();
+        match cell.cell_type.as_str() {
+            "code" => lines.extend(
+                source
+                    .lines()
+                    .map(str::to_string)
+                    .zip(line_kinds(&source, &kernel, blank)),
+            ),
+            "markdown" => lines.extend(source.lines().map(|line| {
+                let kind = if blank.trim(line).is_empty() {
+                    LineKind::Blank
+                } else {
+                    LineKind::Doc
+                };
+                (line.to_string(), kind)
+            })),
+            _ => {}
+        }
+    }
+    Ok(lines)
+}
+
+/// Kernel language from the notebook metadata; notebooks in a language qcount
+/// does not know count every non-blank code line as code.
+fn kernel_language(metadata: &Metadata) -> Language {
+    let name = metadata
+        .language_info
+        .as_ref()
+        .and_then(|l| l.name.as_deref())
+        .or_else(|| {
+            metadata
+                .kernelspec
+                .as_ref()
+                .and_then(|k| k.language.as_deref())
+        });
+    name.and_then(language_from_name)
+        .unwrap_or(Language::Jupyter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n", "\n", "Some prose"]},
+  {"cell_type": "code", "execution_count": 1, "metadata": {},
+   "outputs": [{"output_type": "stream", "name": "stdout", "text": ["3\n", "4\n"]}],
+   "source": ["# TODO: tidy\n", "x = 1\n", "\n", "print(x + 2)"]},
+  {"cell_type": "raw", "metadata": {}, "source": "ignored\n"},
+  {"cell_type": "code", "metadata": {}, "outputs": [], "source": "y = 2\n"}
+ ],
+ "metadata": {"kernelspec": {"name": "python3", "language": "python"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##;
+
+    #[test]
+    fn test_counts_cells_by_kind() {
+        let options = CountOptions {
+            markers: vec!["TODO".into()],
+            list_markers: true,
+            ..CountOptions::default()
+        };
+        let s = count_notebook(NOTEBOOK, Path::new("nb.ipynb"), &options).unwrap();
+        assert_eq!(s.language, Language::Jupyter);
+        assert_eq!(s.cells, 3);
+        assert_eq!(s.total_lines, 8);
+        assert_eq!(s.code_lines, 3);
+        // `# TODO: tidy` in Python; the markdown lines are documentation
+        assert_eq!(s.comment_lines, 1);
+        assert_eq!(s.doc_lines, 2);
+        assert_eq!(s.blank_lines, 2);
+        assert_eq!(s.markers["TODO"], 1);
+        assert_eq!(s.marker_hits[0].line, 4);
+    }
+
+    #[test]
+    fn test_source_lines_match_counts() {
+        let lines = source_lines(NOTEBOOK, BlankPolicy::Unicode).unwrap();
+        let s = count_notebook(NOTEBOOK, Path::new("nb.ipynb"), &CountOptions::default()).unwrap();
+        assert_eq!(lines.len() as u64, s.total_lines);
+        let kinds = |kind| lines.iter().filter(|(_, k)| *k == kind).count() as u64;
+        assert_eq!(kinds(LineKind::Code), s.code_lines);
+        assert_eq!(kinds(LineKind::Comment), s.comment_lines);
+        assert_eq!(kinds(LineKind::Doc), s.doc_lines);
+        assert_eq!(lines[3], ("# TODO: tidy".to_string(), LineKind::Comment));
+    }
+
+    #[test]
+    fn test_unknown_kernel_and_invalid_json() {
+        let nb = r##"{"cells": [{"cell_type": "code", "source": "# x\n"}],
+                      "metadata": {"kernelspec": {"language": "julia"}}}"##;
+        let s = count_notebook(nb, Path::new("nb.ipynb"), &CountOptions::default()).unwrap();
+        assert_eq!(s.code_lines, 1);
+        assert_eq!(s.comment_lines, 0);
+
+        assert!(count_notebook("{", Path::new("nb.ipynb"), &CountOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Package manager / build system a manifest belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    Cargo,
+    Npm,
+    Go,
+    Python,
+}
+
+/// A package discovered from a manifest file.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Package {
+    pub name: String,
+    pub kind: PackageKind,
+    /// Directory containing the manifest
+    pub path: PathBuf,
+    /// True for workspace roots that are not packages themselves
+    /// (e.g. a virtual Cargo workspace)
+    pub workspace: bool,
+}
+
+/// Packages keyed by their directory, for nearest-ancestor lookups.
+#[derive(Debug, Default)]
+pub struct PackageIndex {
+    packages: Vec<Package>,
+    by_dir: HashMap<PathBuf, usize>,
+}
+
+impl PackageIndex {
+    /// Builds the index from file paths, parsing every manifest among them.
+    /// When one directory holds several manifests, the first one parsed wins.
+    pub fn discover<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Self {
+        let mut index = PackageIndex::default();
+        for path in paths {
+            if let Some(package) = parse_manifest(path) {
+                index.insert(package);
+            }
+        }
+        index
+    }
+
+    fn insert(&mut self, package: Package) {
+        if self.by_dir.contains_key(&package.path) {
+            return;
+        }
+        self.by_dir
+            .insert(package.path.clone(), self.packages.len());
+        self.packages.push(package);
+    }
+
+    /// Attributes `root` to the nearest package above it, so that a walk
+    /// starting inside a package (`qcount src/`) still finds it. The search
+    /// stops at the repository root (a directory holding `.git`) or the
+    /// filesystem root; a package in `root` itself comes from the walk.
+    pub fn add_enclosing(&mut self, root: &Path) {
+        if self.by_dir.contains_key(root) {
+            return;
+        }
+        let Ok(canonical) = std::fs::canonicalize(root) else {
+            return;
+        };
+        for dir in canonical.ancestors() {
+            let found = (dir != canonical)
+                .then(|| {
+                    MANIFESTS
+                        .iter()
+                        .find_map(|name| parse_manifest(&dir.join(name)))
+                })
+                .flatten();
+            if let Some(package) = found {
+                let i = match self.packages.iter().position(|p| p.path == package.path) {
+                    Some(i) => i,
+                    None => {
+                        self.packages.push(package);
+                        self.packages.len() - 1
+                    }
+                };
+                self.by_dir.insert(root.to_path_buf(), i);
+                return;
+            }
+            if dir.join(".git").exists() {
+                return;
+            }
+        }
+    }
+
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    /// Index of the package whose directory is the nearest ancestor of `path`.
+    pub fn package_of(&self, path: &Path) -> Option<usize> {
+        path.ancestors()
+            .skip(1)
+            .find_map(|dir| self.by_dir.get(dir).copied())
+    }
+}
+
+/// File names recognized as package manifests.
+pub const MANIFESTS: &[&str] = &["Cargo.toml", "package.json", "go.mod", "pyproject.toml"];
+
+/// Parses a manifest file; returns None for non-manifests and manifests
+/// that declare neither a package nor a workspace.
+pub fn parse_manifest(path: &Path) -> Option<Package> {
+    let file_name = path.file_name()?.to_str()?;
+    if !MANIFESTS.contains(&file_name) {
+        return None;
+    }
+    let dir = path.parent()?.to_path_buf();
+    let content = std::fs::read_to_string(path).ok()?;
+
+    let (name, kind, workspace) = match file_name {
+        "Cargo.toml" => parse_cargo(&content)?,
+        "package.json" => parse_npm(&content)?,
+        "go.mod" => (Some(parse_go(&content)?), PackageKind::Go, false),
+        _ => (Some(parse_pyproject(&content)?), PackageKind::Python, false),
+    };
+    let name = name.unwrap_or_else(|| dir_name(&dir));
+
+    Some(Package {
+        name,
+        kind,
+        path: dir,
+        workspace,
+    })
+}
+
+fn dir_name(dir: &Path) -> String {
+    std::fs::canonicalize(dir)
+        .ok()
+        .and_then(|d| d.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+type Parsed = (Option<String>, PackageKind, bool);
+
+fn parse_cargo(content: &str) -> Option<Parsed> {
+    let table: toml::Table = content.parse().ok()?;
+    if let Some(name) = table
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+    {
+        return Some((Some(name.to_string()), PackageKind::Cargo, false));
+    }
+    table
+        .contains_key("workspace")
+        .then_some((None, PackageKind::Cargo, true))
+}
+
+fn parse_npm(content: &str) -> Option<Parsed> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+    let workspace = json.get("workspaces").is_some();
+    match json.get("name").and_then(|n| n.as_str()) {
+        Some(name) => Some((Some(name.to_string()), PackageKind::Npm, workspace)),
+        None if workspace => Some((None, PackageKind::Npm, true)),
+        None => None,
+    }
+}
+
+fn parse_go(content: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("module") {
+            return None;
+        }
+        words.next().map(|m| m.trim_matches('"').to_string())
+    })
+}
+
+fn parse_pyproject(content: &str) -> Option<String> {
+    let table: toml::Table = content.parse().ok()?;
+    let name = table
+        .get("project")
+        .and_then(|p| p.get("name"))
+        .or_else(|| {
+            table
+                .get("tool")
+                .and_then(|t| t.get("poetry"))
+                .and_then(|p| p.get("name"))
+        })?;
+    name.as_str().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo() {
+        let parsed = parse_cargo("[package]\nname = \"core\"\nversion = \"0.1.0\"\n");
+        assert_eq!(
+            parsed,
+            Some((Some("core".into()), PackageKind::Cargo, false))
+        );
+
+        let parsed = parse_cargo("[workspace]\nmembers = [\"crates/*\"]\n");
+        assert_eq!(parsed, Some((None, PackageKind::Cargo, true)));
+
+        assert_eq!(parse_cargo("[dependencies]\n"), None);
+    }
+
+    #[test]
+    fn test_parse_npm() {
+        let parsed = parse_npm(r#"{"name": "@acme/web", "workspaces": ["packages/*"]}"#);
+        assert_eq!(
+            parsed,
+            Some((Some("@acme/web".into()), PackageKind::Npm, true))
+        );
+        assert_eq!(parse_npm(r#"{"private": true}"#), None);
+    }
+
+    #[test]
+    fn test_parse_go_and_pyproject() {
+        assert_eq!(
+            parse_go("// comment\nmodule github.com/acme/api\n\ngo 1.22\n"),
+            Some("github.com/acme/api".into())
+        );
+        assert_eq!(
+            parse_pyproject("[project]\nname = \"tools\"\n"),
+            Some("tools".into())
+        );
+        assert_eq!(
+            parse_pyproject("[tool.poetry]\nname = \"legacy\"\n"),
+            Some("legacy".into())
+        );
+    }
+
+    #[test]
+    fn test_nearest_package() {
+        let mut index = PackageIndex::default();
+        for (name, path) in [("root", "repo"), ("api", "repo/services/api")] {
+            index.insert(Package {
+                name: name.into(),
+                kind: PackageKind::Cargo,
+                path: PathBuf::from(path),
+                workspace: false,
+            });
+        }
+        assert_eq!(
+            index.package_of(Path::new("repo/services/api/src/main.rs")),
+            Some(1)
+        );
+        assert_eq!(index.package_of(Path::new("repo/scripts/x.sh")), Some(0));
+        assert_eq!(index.package_of(Path::new("other/x.sh")), None);
+    }
+
+    #[test]
+    fn test_enclosing_package_stops_at_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let outer = dir.path();
+        std::fs::write(outer.join("package.json"), r#"{"name": "outer"}"#).unwrap();
+        let repo = outer.join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("app").join("src")).unwrap();
+        std::fs::write(
+            repo.join("app").join("Cargo.toml"),
+            "[package]\nname = \"app\"\n",
+        )
+        .unwrap();
+
+        let src = repo.join("app").join("src");
+        let mut index = PackageIndex::default();
+        index.add_enclosing(&src);
+        assert_eq!(index.package_of(&src.join("lib.rs")), Some(0));
+        assert_eq!(index.packages()[0].name, "app");
+
+        // Nothing between the root and `.git`: the outer package is not used
+        let mut index = PackageIndex::default();
+        index.add_enclosing(&repo);
+        assert!(index.packages().is_empty());
+    }
+}
@@ -0,0 +1,320 @@
+use crate::aggregator::{Accumulator, Totals};
+use crate::counter::{self, CountOptions, FileStats};
+use crate::dedupe::Dedupe;
+use crate::language;
+use crate::language::Language;
+use crate::package::MANIFESTS;
+use crate::walker::{self, WalkOptions};
+use anyhow::Result;
+use ignore::{DirEntry, ParallelVisitor, ParallelVisitorBuilder, WalkState};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// What to walk and what to keep while counting.
+#[derive(Debug, Clone)]
+pub struct PipelineOptions {
+    pub roots: Vec<PathBuf>,
+    pub walk: WalkOptions,
+    pub count: CountOptions,
+    /// Extension -> language mappings that take precedence over the built-in ones
+    pub language_overrides: HashMap<String, Language>,
+    pub by_dir: bool,
+    /// Keep every `FileStats` for passes that need the whole file list
+    /// (e.g. `--by-package`); off by default to bound memory
+    pub retain_files: bool,
+    /// Record the package manifests in the directories between each counted
+    /// file and its root, so that `--include` does not hide them
+    pub collect_manifests: bool,
+    /// Count hardlinks and files with identical contents only once
+    pub dedupe: bool,
+    pub threads: usize,
+}
+
+/// Result of a walk: the merged summary plus whatever was asked to be kept.
+#[derive(Debug, Default)]
+pub struct Collected {
+    pub summary: Accumulator,
+    pub files: Vec<FileStats>,
+    pub manifests: Vec<PathBuf>,
+    /// Files of a known language that could not be read
+    pub skipped: u64,
+    /// Copies skipped with `dedupe`, with the lines they would have added
+    pub duplicates: Totals,
+}
+
+impl Collected {
+    fn new(options: &PipelineOptions) -> Self {
+        Collected {
+            summary: Accumulator::new(options.by_dir, &options.roots),
+            ..Collected::default()
+        }
+    }
+
+    fn merge(&mut self, other: Collected) {
+        self.summary.merge(other.summary);
+        self.files.extend(other.files);
+        self.manifests.extend(other.manifests);
+        self.skipped += other.skipped;
+        self.duplicates.merge(&other.duplicates);
+    }
+}
+
+/// Walks all roots with `ignore`'s parallel walker and counts files as they
+/// are discovered. Each walker thread folds into its own `Collected`, which
+/// is merged into the result when the thread finishes.
+pub fn run(options: &PipelineOptions) -> Result<Collected> {
+    let sink = Mutex::new(Collected::new(options));
+    // Files reachable from several roots or through symlinks are counted once
+    let dedupe = options.roots.len() > 1 || options.walk.follow_links;
+    let seen = dedupe.then(|| Mutex::new(HashSet::new()));
+    let contents = options.dedupe.then(Dedupe::new);
+
+    let mut builder = walker::builder(&options.roots, &options.walk)?;
+    builder.threads(options.threads);
+    builder.build_parallel().visit(&mut VisitorBuilder {
+        options,
+        seen: seen.as_ref(),
+        contents: contents.as_ref(),
+        sink: &sink,
+    });
+
+    let mut collected = sink.into_inner().unwrap();
+    // Threads search the same parent directories independently
+    collected.manifests.sort();
+    collected.manifests.dedup();
+    Ok(collected)
+}
+
+struct VisitorBuilder<'s> {
+    options: &'s PipelineOptions,
+    seen: Option<&'s Mutex<HashSet<PathBuf>>>,
+    contents: Option<&'s Dedupe>,
+    sink: &'s Mutex<Collected>,
+}
+
+impl<'s> ParallelVisitorBuilder<'s> for VisitorBuilder<'s> {
+    fn build(&mut self) -> Box<dyn ParallelVisitor + 's> {
+        Box::new(Visitor {
+            options: self.options,
+            seen: self.seen,
+            contents: self.contents,
+            sink: self.sink,
+            local: Collected::new(self.options),
+            searched_dirs: HashSet::new(),
+        })
+    }
+}
+
+struct Visitor<'s> {
+    options: &'s PipelineOptions,
+    seen: Option<&'s Mutex<HashSet<PathBuf>>>,
+    contents: Option<&'s Dedupe>,
+    sink: &'s Mutex<Collected>,
+    local: Collected,
+    /// Directories already searched for manifests by this thread
+    searched_dirs: HashSet<PathBuf>,
+}
+
+impl Visitor<'_> {
+    fn first_visit(&self, entry: &DirEntry) -> bool {
+        let Some(seen) = self.seen else {
+            return true;
+        };
+        let key = std::fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().into());
+        seen.lock().unwrap().insert(key)
+    }
+
+    fn find_manifests(&mut self, path: &Path, root: &Path) {
+        for dir in path.ancestors().skip(1) {
+            if !dir.starts_with(root) || !self.searched_dirs.insert(dir.to_path_buf()) {
+                break;
+            }
+            for name in MANIFESTS {
+                let manifest = dir.join(name);
+                if manifest.is_file() {
+                    self.local.manifests.push(manifest);
+                }
+            }
+        }
+    }
+
+    fn count(&mut self, entry: &DirEntry) {
+        let path = entry.path();
+        let Some(lang) = language::language_of(path, &self.options.language_overrides) else {
+            return;
+        };
+        let root = self
+            .options
+            .roots
+            .iter()
+            .find(|r| path.starts_with(r))
+            .unwrap_or(&self.options.roots[0]);
+        if self.options.collect_manifests {
+            self.find_manifests(path, root);
+        }
+
+        match counter::count(path, root, lang, &self.options.count) {
+            Ok(stats) => {
+                if let Some(contents) = self.contents {
+                    match contents.is_duplicate(path, stats.language.clone()) {
+                        Ok(false) => {}
+                        Ok(true) => {
+                            self.local.duplicates.add(&stats);
+                            return;
+                        }
+                        Err(_) => {
+                            self.local.skipped += 1;
+                            return;
+                        }
+                    }
+                }
+                self.local.summary.add(&stats);
+                if self.options.retain_files {
+                    self.local.files.push(stats);
+                }
+            }
+            Err(_) => self.local.skipped += 1,
+        }
+    }
+}
+
+impl ParallelVisitor for Visitor<'_> {
+    fn visit(&mut self, entry: Result<DirEntry, ignore::Error>) -> WalkState {
+        if let Ok(entry) = entry
+            && entry.file_type().is_some_and(|t| t.is_file())
+            && self.first_visit(&entry)
+        {
+            self.count(&entry);
+        }
+        WalkState::Continue
+    }
+}
+
+impl Drop for Visitor<'_> {
+    fn drop(&mut self) {
+        let local = std::mem::take(&mut self.local);
+        if let Ok(mut sink) = self.sink.lock() {
+            sink.merge(local);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregator::Meta;
+
+    fn options(roots: Vec<PathBuf>) -> PipelineOptions {
+        PipelineOptions {
+            roots,
+            walk: WalkOptions::default(),
+            language_overrides: HashMap::new(),
+            count: CountOptions::default(),
+            by_dir: false,
+            retain_files: true,
+            collect_manifests: true,
+            dedupe: false,
+            threads: 4,
+        }
+    }
+
+    #[test]
+    fn test_counts_and_dedupes_overlapping_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        std::fs::write(sub.join("b.rs"), "fn b() {}\n\n").unwrap();
+        std::fs::write(sub.join("notes.txt"), "not counted\n").unwrap();
+
+        let roots = vec![sub.clone(), dir.path().to_path_buf()];
+        let collected = run(&options(roots.clone())).unwrap();
+
+        assert_eq!(collected.files.len(), 3);
+        assert_eq!(collected.manifests.len(), 1);
+        assert_eq!(collected.skipped, 0);
+
+        let report = collected.summary.finish(Meta {
+            roots,
+            ..Meta::default()
+        });
+        assert_eq!(report.total.files, 3);
+        assert_eq!(report.by_root[0].total.files, 1);
+        assert_eq!(report.by_root[1].total.files, 2);
+    }
+
+    #[test]
+    fn test_manifests_are_found_outside_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("crate").join("src");
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(dir.path().join("package.json"), "{}\n").unwrap();
+        std::fs::write(src.join("lib.rs"), "fn a() {}\n").unwrap();
+        std::fs::write(src.join("main.rs"), "fn main() {}\n").unwrap();
+        let manifest = dir.path().join("crate").join("Cargo.toml");
+        std::fs::write(&manifest, "[package]\n").unwrap();
+
+        let mut opts = options(vec![dir.path().to_path_buf()]);
+        opts.walk.includes = vec!["*.rs".to_string()];
+        let collected = run(&opts).unwrap();
+
+        assert_eq!(collected.files.len(), 2);
+        assert_eq!(
+            collected.manifests,
+            [manifest, dir.path().join("package.json")]
+        );
+    }
+
+    #[test]
+    fn test_files_are_not_retained_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.py"), "x = 1\n").unwrap();
+        std::fs::write(dir.path().join("bad.py"), [0xff, 0xfe, b'\n']).unwrap();
+
+        let mut opts = options(vec![dir.path().to_path_buf()]);
+        opts.retain_files = false;
+        let collected = run(&opts).unwrap();
+
+        assert!(collected.files.is_empty());
+        assert_eq!(collected.skipped, 1);
+        assert_eq!(collected.summary.finish(Meta::default()).total.files, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_links_counts_target_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("real");
+        std::fs::create_dir(&real).unwrap();
+        std::fs::write(real.join("a.rs"), "fn a() {}\n").unwrap();
+        std::os::unix::fs::symlink(&real, dir.path().join("link")).unwrap();
+
+        let mut opts = options(vec![dir.path().to_path_buf()]);
+        opts.walk.follow_links = true;
+        let collected = run(&opts).unwrap();
+
+        assert_eq!(collected.files.len(), 1);
+    }
+
+    #[test]
+    fn test_dedupe_skips_copies() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("vendor");
+        std::fs::create_dir(&sub).unwrap();
+        std::fs::write(dir.path().join("a.rs"), "fn a() {}\n\n").unwrap();
+        std::fs::write(sub.join("a.rs"), "fn a() {}\n\n").unwrap();
+        std::fs::write(sub.join("b.rs"), "fn b() {}\n").unwrap();
+
+        let mut opts = options(vec![dir.path().to_path_buf()]);
+        opts.dedupe = true;
+        let collected = run(&opts).unwrap();
+
+        assert_eq!(collected.files.len(), 2);
+        assert_eq!(collected.duplicates.files, 1);
+        assert_eq!(collected.duplicates.total_lines, 2);
+        assert_eq!(collected.duplicates.code_lines, 1);
+        assert_eq!(collected.duplicates.blank_lines, 1);
+    }
+}
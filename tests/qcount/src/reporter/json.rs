@@ -1,13 +1,20 @@
 use crate::aggregator::Report;
 use crate::reporter::Reporter;
 use anyhow::Result;
+use std::io::Write;
 
 pub struct JsonReporter;
 
 impl Reporter for JsonReporter {
-    fn report(&self, report: &Report) -> Result<()> {
-        let json = serde_json::to_string_pretty(report)?;
-        println!("{}", json);
+    fn report(&self, report: &Report, out: &mut dyn Write) -> Result<()> {
+        serde_json::to_writer_pretty(&mut *out, report)?;
+        writeln!(out)?;
         Ok(())
     }
 }
+
+/// JSON Schema (draft 2020-12) describing the `--json` output.
+pub fn schema() -> Result<String> {
+    let schema = schemars::schema_for!(Report);
+    Ok(serde_json::to_string_pretty(&schema)?)
+}
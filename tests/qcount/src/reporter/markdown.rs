@@ -0,0 +1,498 @@
+use crate::aggregator::{LangSummary, Report, Totals};
+use crate::baseline;
+use crate::hygiene::Hygiene;
+use crate::reporter::Reporter;
+use anyhow::Result;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// GitHub-flavored Markdown, e.g. for a CI job summary or a PR comment.
+/// Has the same sections as the table reporter; only the sections filled
+/// in the report are written.
+pub struct MarkdownReporter {
+    /// Also write the bytes / characters / line length table
+    pub text_stats: bool,
+}
+
+impl Reporter for MarkdownReporter {
+    fn report(&self, report: &Report, out: &mut dyn Write) -> Result<()> {
+        let total = &report.total;
+        writeln!(out, "# qcount report\n")?;
+        writeln!(
+            out,
+            "**{}** files, **{}** lines ({} code, {} comment, {} blank; {} test)",
+            total.files,
+            total.total_lines,
+            total.code_lines,
+            total.comment_lines,
+            total.blank_lines,
+            total.test_code_lines
+        )?;
+        if total.cells > 0 {
+            writeln!(
+                out,
+                "\nNotebook cells: {} (code and markdown); {} documentation lines",
+                total.cells, total.doc_lines
+            )?;
+        }
+        if let Some(dup) = &report.duplicates {
+            writeln!(
+                out,
+                "\nDuplicates skipped: {} files, {} lines",
+                dup.files, dup.total_lines
+            )?;
+        }
+        if report.meta.total_only {
+            writeln!(
+                out,
+                "\n_--total-only: comments not classified; Code counts every non-blank line_"
+            )?;
+        }
+
+        writeln!(out, "\n## Languages\n")?;
+        lang_table(out, &report.by_language, total)?;
+
+        if report.meta.hygiene {
+            writeln!(out, "\n## Hygiene\n")?;
+            let mut rows: Vec<Vec<String>> = report
+                .by_language
+                .iter()
+                .map(|l| hygiene_row(l.language.name(), &l.totals.hygiene))
+                .collect();
+            rows.push(hygiene_row("**Total**", &total.hygiene));
+            table(out, &HYGIENE_HEADERS, 1, &rows)?;
+        }
+
+        if report.meta.symbols {
+            writeln!(out, "\n## Symbols\n")?;
+            let mut rows: Vec<Vec<String>> = report
+                .by_language
+                .iter()
+                .map(|l| {
+                    vec![
+                        l.language.name().to_string(),
+                        l.totals.symbols.functions.to_string(),
+                        l.totals.symbols.types.to_string(),
+                        l.totals.symbols.modules.to_string(),
+                    ]
+                })
+                .collect();
+            rows.push(vec![
+                "**Total**".to_string(),
+                total.symbols.functions.to_string(),
+                total.symbols.types.to_string(),
+                total.symbols.modules.to_string(),
+            ]);
+            table(
+                out,
+                &["Language", "Functions", "Types", "Modules"],
+                1,
+                &rows,
+            )?;
+        }
+
+        if !report.meta.markers.is_empty() {
+            writeln!(out, "\n## Markers\n")?;
+            let rows: Vec<Vec<String>> = report
+                .meta
+                .markers
+                .iter()
+                .map(|tag| {
+                    let count = total.markers.get(tag).copied().unwrap_or(0);
+                    vec![format!("`{}`", tag), count.to_string()]
+                })
+                .collect();
+            table(out, &["Marker", "Count"], 1, &rows)?;
+            if !report.marker_hits.is_empty() {
+                writeln!(out)?;
+                for hit in &report.marker_hits {
+                    writeln!(out, "- `{}:{}` {}", hit.path.display(), hit.line, hit.text)?;
+                }
+            }
+        }
+
+        if self.text_stats {
+            writeln!(out, "\n## Text\n")?;
+            let text_row = |label: &str, t: &Totals| {
+                vec![
+                    label.to_string(),
+                    t.text.bytes.to_string(),
+                    t.text.chars.to_string(),
+                    t.text.max_line_length.to_string(),
+                    format!("{:.1}", t.text.mean_line_length),
+                    t.text.long_lines.to_string(),
+                ]
+            };
+            let mut rows: Vec<Vec<String>> = report
+                .by_language
+                .iter()
+                .map(|l| text_row(l.language.name(), &l.totals))
+                .collect();
+            rows.push(text_row("**Total**", total));
+            let long_lines = format!("Lines > {}", report.meta.long_line_width);
+            table(
+                out,
+                &[
+                    "Language",
+                    "Bytes",
+                    "Chars",
+                    "Max Len",
+                    "Mean Len",
+                    &long_lines,
+                ],
+                1,
+                &rows,
+            )?;
+        }
+
+        if let Some(churn) = &report.churn {
+            writeln!(out, "\n## Churn since `{}`\n", churn.base)?;
+            let cell = |c: &crate::churn::LineChurn| {
+                format!("+{} -{} ~{}", c.added, c.removed, c.modified)
+            };
+            let mut rows: Vec<Vec<String>> = churn
+                .by_language
+                .iter()
+                .map(|l| {
+                    vec![
+                        l.language.name().to_string(),
+                        l.files.to_string(),
+                        cell(&l.churn.code),
+                        cell(&l.churn.comment),
+                        cell(&l.churn.blank),
+                        cell(&l.churn.doc),
+                    ]
+                })
+                .collect();
+            rows.push(vec![
+                "**Total**".to_string(),
+                churn.files.to_string(),
+                cell(&churn.total.code),
+                cell(&churn.total.comment),
+                cell(&churn.total.blank),
+                cell(&churn.total.doc),
+            ]);
+            table(
+                out,
+                &["Language", "Files", "Code", "Comment", "Blank", "Doc"],
+                1,
+                &rows,
+            )?;
+        }
+
+        if let Some(check) = &report.baseline {
+            writeln!(
+                out,
+                "\n## Baseline `{}`\n\n{} rules, {} regressed{}\n",
+                check.file.display(),
+                check.rules.len(),
+                check.regressed,
+                if check.updated { " (updated)" } else { "" }
+            )?;
+            let rows: Vec<Vec<String>> = check
+                .rules
+                .iter()
+                .map(|r| {
+                    vec![
+                        format!("`{}`", r.scope()),
+                        r.metric.name().to_string(),
+                        r.ratchet.name().to_string(),
+                        r.baseline.map_or("-".to_string(), baseline::format_value),
+                        baseline::format_value(r.current),
+                        r.status.name().to_string(),
+                    ]
+                })
+                .collect();
+            table(
+                out,
+                &[
+                    "Scope", "Metric", "Ratchet", "Baseline", "Current", "Status",
+                ],
+                3,
+                &rows,
+            )?;
+        }
+
+        if let Some(e) = &report.estimate {
+            writeln!(out, "\n## Estimate ({})\n", e.mode.label())?;
+            writeln!(out, "- Cost to develop: ${:.0}", e.cost)?;
+            writeln!(out, "- Schedule: {:.2} months", e.schedule_months)?;
+            writeln!(out, "- People: {:.2}", e.people)?;
+            writeln!(
+                out,
+                "- Effort: {:.2} person-months (salary {:.0}, overhead {:.2})",
+                e.effort_person_months, e.salary, e.overhead
+            )?;
+        }
+
+        if !report.by_root.is_empty() {
+            writeln!(out, "\n## Roots")?;
+            for root in &report.by_root {
+                writeln!(out, "\n### `{}`\n", root.root.display())?;
+                lang_table(out, &root.by_language, &root.total)?;
+            }
+        }
+
+        if !report.by_package.is_empty() {
+            writeln!(out, "\n## Packages")?;
+            for pkg in &report.by_package {
+                match &pkg.package {
+                    Some(p) => writeln!(
+                        out,
+                        "\n### {} ({:?}{}) `{}`\n",
+                        p.name,
+                        p.kind,
+                        if p.workspace { " workspace" } else { "" },
+                        p.path.display()
+                    )?,
+                    None => writeln!(out, "\n### (none)\n")?,
+                }
+                lang_table(out, &pkg.by_language, &pkg.total)?;
+            }
+        }
+
+        if !report.by_owner.is_empty() {
+            writeln!(out, "\n## Owners")?;
+            for owner in &report.by_owner {
+                writeln!(out, "\n### {}\n", owner.owner)?;
+                lang_table(out, &owner.by_language, &owner.total)?;
+            }
+        }
+        file_list(out, "Unowned files", &report.unowned_files)?;
+
+        if !report.by_license.is_empty() {
+            writeln!(out, "\n## Licenses")?;
+            for license in &report.by_license {
+                writeln!(
+                    out,
+                    "\n### {} ({} files)\n",
+                    license.license, license.total.files
+                )?;
+                lang_table(out, &license.by_language, &license.total)?;
+            }
+        }
+        file_list(
+            out,
+            "Files without license header",
+            &report.unlicensed_files,
+        )?;
+
+        if !report.by_directory.is_empty() {
+            writeln!(out, "\n## Directories\n")?;
+            let rows: Vec<Vec<String>> = report
+                .by_directory
+                .iter()
+                .map(|dir| {
+                    let mut sum = [0; 6];
+                    for lang in dir.by_language.values() {
+                        for (s, n) in sum.iter_mut().zip(counts(&lang.totals)) {
+                            *s += n;
+                        }
+                    }
+                    row(&format!("`{}`", dir.path.display()), sum)
+                })
+                .collect();
+            table(out, &DIR_HEADERS, 1, &rows)?;
+        }
+
+        if !report.by_file.is_empty() {
+            writeln!(out, "\n## Files\n")?;
+            let rows: Vec<Vec<String>> = report
+                .by_file
+                .iter()
+                .map(|f| {
+                    vec![
+                        format!("`{}`", f.path.display()),
+                        f.language.name().to_string(),
+                        f.total_lines.to_string(),
+                        f.code_lines.to_string(),
+                        f.comment_lines.to_string(),
+                        f.blank_lines.to_string(),
+                    ]
+                })
+                .collect();
+            table(
+                out,
+                &["File", "Language", "Total", "Code", "Comment", "Blank"],
+                2,
+                &rows,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+const LANG_HEADERS: [&str; 7] = [
+    "Language", "Files", "Total", "Code", "Comment", "Blank", "Test",
+];
+const DIR_HEADERS: [&str; 7] = [
+    "Directory",
+    "Files",
+    "Total",
+    "Code",
+    "Comment",
+    "Blank",
+    "Test",
+];
+
+/// Files, total, code, comment, blank and test lines, in table order.
+fn counts(t: &Totals) -> [u64; 6] {
+    [
+        t.files,
+        t.total_lines,
+        t.code_lines,
+        t.comment_lines,
+        t.blank_lines,
+        t.test_code_lines,
+    ]
+}
+
+const HYGIENE_HEADERS: [&str; 10] = [
+    "Language",
+    "LF",
+    "CRLF",
+    "CR",
+    "Mixed EOL",
+    "No Final NL",
+    "Trailing WS Lines",
+    "Tab Indent",
+    "Space Indent",
+    "Mixed Indent",
+];
+
+/// Per-language rows followed by a total row.
+fn lang_table(out: &mut dyn Write, langs: &[LangSummary], total: &Totals) -> Result<()> {
+    let mut rows: Vec<Vec<String>> = langs
+        .iter()
+        .map(|l| row(l.language.name(), counts(&l.totals)))
+        .collect();
+    rows.push(row("**Total**", counts(total)));
+    table(out, &LANG_HEADERS, 1, &rows)
+}
+
+fn hygiene_row(label: &str, h: &Hygiene) -> Vec<String> {
+    std::iter::once(label.to_string())
+        .chain(
+            [
+                h.lf_files,
+                h.crlf_files,
+                h.cr_files,
+                h.mixed_line_ending_files,
+                h.no_final_newline_files,
+                h.trailing_whitespace_lines,
+                h.tab_indented_lines,
+                h.space_indented_lines,
+                h.mixed_indent_files,
+            ]
+            .iter()
+            .map(u64::to_string),
+        )
+        .collect()
+}
+
+/// A titled bullet list of paths; nothing when `paths` is empty.
+fn file_list(out: &mut dyn Write, title: &str, paths: &[PathBuf]) -> Result<()> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "\n## {} ({})\n", title, paths.len())?;
+    for path in paths {
+        writeln!(out, "- `{}`", path.display())?;
+    }
+    Ok(())
+}
+
+fn row(label: &str, counts: [u64; 6]) -> Vec<String> {
+    std::iter::once(label.to_string())
+        .chain(counts.iter().map(u64::to_string))
+        .collect()
+}
+
+/// Writes a table whose first `labels` columns are left-aligned text and
+/// the others right-aligned numbers.
+fn table(out: &mut dyn Write, headers: &[&str], labels: usize, rows: &[Vec<String>]) -> Result<()> {
+    writeln!(out, "| {} |", headers.join(" | "))?;
+    let align: Vec<&str> = (0..headers.len())
+        .map(|i| if i < labels { "---" } else { "---:" })
+        .collect();
+    writeln!(out, "| {} |", align.join(" | "))?;
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|c| escape(c)).collect();
+        writeln!(out, "| {} |", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+/// Escapes characters that would end a table cell.
+fn escape(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregator::{Accumulator, Meta};
+    use crate::counter::{CountOptions, count_content};
+    use crate::estimate;
+    use crate::language::Language;
+    use std::path::Path;
+
+    fn render(report: &Report) -> String {
+        let mut out = Vec::new();
+        MarkdownReporter { text_stats: false }
+            .report(report, &mut out)
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_languages_table() {
+        let mut acc = Accumulator::new(false, &[]);
+        let options = CountOptions::default();
+        acc.add(&count_content(
+            "// c\nfn a() {}\n\n",
+            Path::new("a.rs"),
+            Language::Rust,
+            &options,
+        ));
+        acc.add(&count_content(
+            "x = 1\n",
+            Path::new("b.py"),
+            Language::Python,
+            &options,
+        ));
+        let md = render(&acc.finish(Meta::default()));
+
+        assert!(md.starts_with("# qcount report\n\n**2** files, **4** lines"));
+        assert!(md.contains(
+            "| Language | Files | Total | Code | Comment | Blank | Test |\n\
+             | --- | ---: | ---: | ---: | ---: | ---: | ---: |\n"
+        ));
+        assert!(md.contains("| Rust | 1 | 3 | 1 | 1 | 1 | 0 |\n"));
+        assert!(md.contains("| **Total** | 2 | 4 | 2 | 1 | 1 | 0 |\n"));
+        assert!(!md.contains("## Files"));
+    }
+
+    #[test]
+    fn test_estimate_includes_effort() {
+        let mut report = Accumulator::new(false, &[]).finish(Meta::default());
+        let params = estimate::EstimateParams {
+            mode: estimate::CocomoMode::Organic,
+            salary: 56286.0,
+            overhead: 2.4,
+        };
+        let e = estimate::estimate(10_000, &params);
+        let effort = format!(
+            "- Effort: {:.2} person-months (salary 56286, overhead 2.40)\n",
+            e.effort_person_months
+        );
+        report.estimate = Some(e);
+        assert!(render(&report).contains(&effort));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("`a|b`"), "`a\\|b`");
+    }
+}
@@ -1,9 +1,25 @@
 use crate::aggregator::Report;
+use crate::config::OutputFormat;
 use anyhow::Result;
+use std::io::Write;
 
 pub mod json;
+pub mod markdown;
+pub mod openmetrics;
 pub mod table;
 
 pub trait Reporter {
-    fn report(&self, report: &Report) -> Result<()>;
+    /// Renders `report` into `out` (stdout, a file, a buffer, ...).
+    fn report(&self, report: &Report, out: &mut dyn Write) -> Result<()>;
+}
+
+/// Reporter for an output format. `text_stats` only affects the table and
+/// Markdown formats.
+pub fn for_format(format: OutputFormat, text_stats: bool) -> Box<dyn Reporter> {
+    match format {
+        OutputFormat::Table => Box::new(table::TableReporter { text_stats }),
+        OutputFormat::Json => Box::new(json::JsonReporter),
+        OutputFormat::Openmetrics => Box::new(openmetrics::OpenMetricsReporter),
+        OutputFormat::Markdown => Box::new(markdown::MarkdownReporter { text_stats }),
+    }
 }
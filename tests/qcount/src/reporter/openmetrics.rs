@@ -0,0 +1,294 @@
+use crate::aggregator::{LangSummary, Report};
+use crate::reporter::Reporter;
+use anyhow::Result;
+use std::fmt::Write;
+use std::io;
+
+/// Prometheus / OpenMetrics text exposition, suitable for the node-exporter
+/// textfile collector.
+pub struct OpenMetricsReporter;
+
+impl Reporter for OpenMetricsReporter {
+    fn report(&self, report: &Report, out: &mut dyn io::Write) -> Result<()> {
+        out.write_all(render(report).as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Renders the report. Series are sorted by their labels so that repeated
+/// runs over the same tree produce identical output.
+pub fn render(report: &Report) -> String {
+    let mut out = String::new();
+
+    family(&mut out, "qcount_files", "Files counted per language.");
+    for lang in sorted(report.by_language.iter()) {
+        sample(
+            &mut out,
+            "qcount_files",
+            &[("language", lang.language.name())],
+            lang.totals.files,
+        );
+    }
+
+    // `directory` is present only with --by-dir; per-directory series then
+    // replace the per-language ones so sums stay correct.
+    family(
+        &mut out,
+        "qcount_lines",
+        "Lines per language and kind (code, comment, blank; doc for notebooks).",
+    );
+    if report.by_directory.is_empty() {
+        for lang in sorted(report.by_language.iter()) {
+            lines(&mut out, &[("language", lang.language.name())], lang);
+        }
+    } else {
+        for dir in &report.by_directory {
+            let directory = dir.path.display().to_string();
+            for lang in sorted(dir.by_language.values()) {
+                let labels = [
+                    ("directory", directory.as_str()),
+                    ("language", lang.language.name()),
+                ];
+                lines(&mut out, &labels, lang);
+            }
+        }
+    }
+
+    family(
+        &mut out,
+        "qcount_test_code_lines",
+        "Code lines that belong to tests, per language (a subset of the code lines).",
+    );
+    for lang in sorted(report.by_language.iter()) {
+        let labels = [("language", lang.language.name())];
+        sample(
+            &mut out,
+            "qcount_test_code_lines",
+            &labels,
+            lang.totals.test_code_lines,
+        );
+    }
+
+    if report.meta.symbols {
+        family(
+            &mut out,
+            "qcount_symbols",
+            "Declarations per language and kind (function, type, module).",
+        );
+        for lang in sorted(report.by_language.iter()) {
+            for (kind, value) in [
+                ("function", lang.totals.symbols.functions),
+                ("type", lang.totals.symbols.types),
+                ("module", lang.totals.symbols.modules),
+            ] {
+                let labels = [("language", lang.language.name()), ("kind", kind)];
+                sample(&mut out, "qcount_symbols", &labels, value);
+            }
+        }
+    }
+
+    family(&mut out, "qcount_bytes", "Bytes per language.");
+    for lang in sorted(report.by_language.iter()) {
+        sample(
+            &mut out,
+            "qcount_bytes",
+            &[("language", lang.language.name())],
+            lang.totals.text.bytes,
+        );
+    }
+
+    family(
+        &mut out,
+        "qcount_skipped_files",
+        "Files of a known language that could not be read.",
+    );
+    sample(
+        &mut out,
+        "qcount_skipped_files",
+        &[],
+        report.meta.skipped_files,
+    );
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn sorted<'a>(langs: impl Iterator<Item = &'a LangSummary>) -> Vec<&'a LangSummary> {
+    let mut langs: Vec<&LangSummary> = langs.collect();
+    langs.sort_by_key(|l| l.language.name());
+    langs
+}
+
+fn family(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, escape(help));
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+}
+
+/// Code, comment and blank lines; documentation lines only for the
+/// languages that have them (notebooks).
+fn lines(out: &mut String, labels: &[(&str, &str)], lang: &LangSummary) {
+    let doc = (lang.totals.doc_lines > 0).then_some(("doc", lang.totals.doc_lines));
+    for (kind, value) in [
+        ("code", lang.totals.code_lines),
+        ("comment", lang.totals.comment_lines),
+        ("blank", lang.totals.blank_lines),
+    ]
+    .into_iter()
+    .chain(doc)
+    {
+        let mut labels = labels.to_vec();
+        labels.push(("kind", kind));
+        sample(out, "qcount_lines", &labels, value);
+    }
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: u64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+/// Escapes a label value or HELP text: backslash, double quote and line feed.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregator::{Accumulator, Meta};
+    use crate::counter::{CountOptions, count_content};
+    use crate::language::Language;
+    use std::path::Path;
+
+    /// Reads an escaped string up to the closing `"` of a label value, or to
+    /// the end of HELP text; panics on a raw `"` in HELP or a bad escape.
+    fn unescape(chars: &mut std::str::Chars, quoted: bool) -> String {
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('\\') => value.push('\\'),
+                    Some('"') => value.push('"'),
+                    Some('n') => value.push('\n'),
+                    other => panic!("invalid escape \\{:?}", other),
+                },
+                '"' if quoted => return value,
+                '"' => panic!("unescaped quote in HELP text"),
+                c => value.push(c),
+            }
+        }
+        assert!(!quoted, "unterminated label value");
+        value
+    }
+
+    /// Metric name, labels and value of one sample line.
+    type Sample = (String, Vec<(String, String)>, u64);
+
+    /// Minimal OpenMetrics text parser: checks HELP/TYPE/sample ordering,
+    /// escaping and the trailing `# EOF`, and returns the parsed samples.
+    fn parse(text: &str) -> Vec<Sample> {
+        let body = text.strip_suffix("# EOF\n").expect("ends with # EOF");
+        let mut family = "";
+        let mut samples = Vec::new();
+        for line in body.lines() {
+            if let Some(rest) = line.strip_prefix("# HELP ") {
+                let (name, help) = rest.split_once(' ').unwrap();
+                unescape(&mut help.chars(), false);
+                family = name;
+            } else if let Some(rest) = line.strip_prefix("# TYPE ") {
+                assert_eq!(rest, format!("{} gauge", family));
+            } else {
+                let (series, value) = line.rsplit_once(' ').unwrap();
+                let (name, labels) = match series.split_once('{') {
+                    Some((name, labels)) => (name, labels.strip_suffix('}').unwrap()),
+                    None => (series, ""),
+                };
+                assert_eq!(name, family, "sample outside its family: {}", line);
+                let mut parsed = Vec::new();
+                let mut chars = labels.chars();
+                while !chars.as_str().is_empty() {
+                    let (key, rest) = chars.as_str().split_once("=\"").unwrap();
+                    assert!(key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+                    chars = rest.chars();
+                    parsed.push((key.to_string(), unescape(&mut chars, true)));
+                    chars = chars
+                        .as_str()
+                        .strip_prefix(',')
+                        .unwrap_or(chars.as_str())
+                        .chars();
+                }
+                samples.push((name.to_string(), parsed, value.parse().unwrap()));
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_output_parses_as_exposition_format() {
+        let mut acc = Accumulator::new(true, &[]);
+        let options = CountOptions::default();
+        acc.add(&count_content(
+            "// c\nfn a() {}\n",
+            Path::new("src/we\"ird\\dir/a.rs"),
+            Language::Rust,
+            &options,
+        ));
+        let samples = parse(&render(&acc.finish(Meta::default())));
+
+        let code = samples
+            .iter()
+            .find(|(name, labels, _)| {
+                name == "qcount_lines" && labels.contains(&("kind".to_string(), "code".to_string()))
+            })
+            .unwrap();
+        assert_eq!(
+            code.1[0],
+            ("directory".to_string(), "src/we\"ird\\dir".to_string())
+        );
+        assert_eq!(code.2, 1);
+        assert!(
+            samples
+                .iter()
+                .any(|(name, _, _)| name == "qcount_skipped_files")
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"a\b"c"#), r#"a\\b\"c"#);
+        assert_eq!(escape("x\ny"), "x\\ny");
+        assert_eq!(escape("src/ü"), "src/ü");
+    }
+
+    #[test]
+    fn test_sample_format() {
+        let mut out = String::new();
+        sample(
+            &mut out,
+            "qcount_lines",
+            &[("language", "C++"), ("kind", "code")],
+            42,
+        );
+        sample(&mut out, "qcount_skipped_files", &[], 0);
+        assert_eq!(
+            out,
+            "qcount_lines{language=\"C++\",kind=\"code\"} 42\nqcount_skipped_files 0\n"
+        );
+    }
+}
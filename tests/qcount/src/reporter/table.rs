@@ -1,9 +1,20 @@
 use crate::aggregator::{LangSummary, Report};
+use crate::baseline::{self, RuleResult};
+use crate::churn::KindChurn;
+use crate::counter::FileStats;
+use crate::hygiene::Hygiene;
+use crate::marker::MarkerCounts;
 use crate::reporter::Reporter;
+use crate::symbol::Symbols;
 use anyhow::Result;
+use std::io::Write;
+use tabled::builder::Builder;
 use tabled::{Table, Tabled};
 
-pub struct TableReporter;
+pub struct TableReporter {
+    /// Also print the bytes / characters / line length table
+    pub text_stats: bool,
+}
 
 #[derive(Tabled)]
 struct LangRow {
@@ -19,50 +30,482 @@ struct LangRow {
     blank: u64,
     #[tabled(rename = "Comment")]
     comment: u64,
+    #[tabled(rename = "Prod")]
+    production: u64,
+    #[tabled(rename = "Test")]
+    test: u64,
+    #[tabled(rename = "Test %")]
+    test_ratio: String,
 }
 
 impl From<&LangSummary> for LangRow {
     fn from(s: &LangSummary) -> Self {
+        let t = &s.totals;
         LangRow {
             language: format!("{:?}", s.language),
-            files: s.files,
+            files: t.files,
+            total: t.total_lines,
+            code: t.code_lines,
+            blank: t.blank_lines,
+            comment: t.comment_lines,
+            production: t.production_code_lines,
+            test: t.test_code_lines,
+            test_ratio: percent(t.test_code_lines, t.code_lines),
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct FileRow {
+    #[tabled(rename = "File")]
+    path: String,
+    #[tabled(rename = "Language")]
+    language: String,
+    #[tabled(rename = "Total")]
+    total: u64,
+    #[tabled(rename = "Code")]
+    code: u64,
+    #[tabled(rename = "Blank")]
+    blank: u64,
+    #[tabled(rename = "Comment")]
+    comment: u64,
+    #[tabled(rename = "Test")]
+    test: u64,
+}
+
+impl From<&FileStats> for FileRow {
+    fn from(s: &FileStats) -> Self {
+        FileRow {
+            path: s.path.display().to_string(),
+            language: s.language.name().to_string(),
             total: s.total_lines,
             code: s.code_lines,
             blank: s.blank_lines,
             comment: s.comment_lines,
+            test: s.test_code_lines,
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct TextRow {
+    #[tabled(rename = "Language")]
+    language: String,
+    #[tabled(rename = "Bytes")]
+    bytes: u64,
+    #[tabled(rename = "Chars")]
+    chars: u64,
+    #[tabled(rename = "Max Len")]
+    max_line_length: u64,
+    #[tabled(rename = "Mean Len")]
+    mean_line_length: String,
+    #[tabled(rename = "Long Lines")]
+    long_lines: u64,
+}
+
+impl From<&LangSummary> for TextRow {
+    fn from(s: &LangSummary) -> Self {
+        let t = &s.totals;
+        TextRow {
+            language: s.language.name().to_string(),
+            bytes: t.text.bytes,
+            chars: t.text.chars,
+            max_line_length: t.text.max_line_length,
+            mean_line_length: format!("{:.1}", t.text.mean_line_length),
+            long_lines: t.text.long_lines,
         }
     }
 }
 
+#[derive(Tabled)]
+struct HygieneRow {
+    #[tabled(rename = "Language")]
+    language: String,
+    #[tabled(rename = "LF")]
+    lf: u64,
+    #[tabled(rename = "CRLF")]
+    crlf: u64,
+    #[tabled(rename = "CR")]
+    cr: u64,
+    #[tabled(rename = "Mixed EOL")]
+    mixed: u64,
+    #[tabled(rename = "No Final NL")]
+    no_final_newline: u64,
+    #[tabled(rename = "Trailing WS Lines")]
+    trailing_whitespace: u64,
+    #[tabled(rename = "Tab Indent")]
+    tab_indented: u64,
+    #[tabled(rename = "Space Indent")]
+    space_indented: u64,
+    #[tabled(rename = "Mixed Indent")]
+    mixed_indent: u64,
+}
+
+impl HygieneRow {
+    fn new(language: &str, h: &Hygiene) -> Self {
+        HygieneRow {
+            language: language.to_string(),
+            lf: h.lf_files,
+            crlf: h.crlf_files,
+            cr: h.cr_files,
+            mixed: h.mixed_line_ending_files,
+            no_final_newline: h.no_final_newline_files,
+            trailing_whitespace: h.trailing_whitespace_lines,
+            tab_indented: h.tab_indented_lines,
+            space_indented: h.space_indented_lines,
+            mixed_indent: h.mixed_indent_files,
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct SymbolRow {
+    #[tabled(rename = "Language")]
+    language: String,
+    #[tabled(rename = "Functions")]
+    functions: u64,
+    #[tabled(rename = "Types")]
+    types: u64,
+    #[tabled(rename = "Modules")]
+    modules: u64,
+}
+
+impl SymbolRow {
+    fn new(language: &str, s: &Symbols) -> Self {
+        SymbolRow {
+            language: language.to_string(),
+            functions: s.functions,
+            types: s.types,
+            modules: s.modules,
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct BaselineRow {
+    #[tabled(rename = "Scope")]
+    scope: String,
+    #[tabled(rename = "Metric")]
+    metric: &'static str,
+    #[tabled(rename = "Ratchet")]
+    ratchet: &'static str,
+    #[tabled(rename = "Baseline")]
+    baseline: String,
+    #[tabled(rename = "Current")]
+    current: String,
+    #[tabled(rename = "Status")]
+    status: &'static str,
+}
+
+impl From<&RuleResult> for BaselineRow {
+    fn from(r: &RuleResult) -> Self {
+        BaselineRow {
+            scope: r.scope(),
+            metric: r.metric.name(),
+            ratchet: r.ratchet.name(),
+            baseline: r.baseline.map_or("-".to_string(), baseline::format_value),
+            current: baseline::format_value(r.current),
+            status: r.status.name(),
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct ChurnRow {
+    #[tabled(rename = "Language")]
+    language: String,
+    #[tabled(rename = "Files")]
+    files: u64,
+    #[tabled(rename = "Code +/-/~")]
+    code: String,
+    #[tabled(rename = "Comment +/-/~")]
+    comment: String,
+    #[tabled(rename = "Blank +/-/~")]
+    blank: String,
+    #[tabled(rename = "Doc +/-/~")]
+    doc: String,
+}
+
+impl ChurnRow {
+    fn new(language: &str, files: u64, churn: &KindChurn) -> Self {
+        let cell =
+            |c: &crate::churn::LineChurn| format!("+{} -{} ~{}", c.added, c.removed, c.modified);
+        ChurnRow {
+            language: language.to_string(),
+            files,
+            code: cell(&churn.code),
+            comment: cell(&churn.comment),
+            blank: cell(&churn.blank),
+            doc: cell(&churn.doc),
+        }
+    }
+}
+
+/// Language x marker tag table; columns are the searched tags.
+fn marker_table(tags: &[String], langs: &[LangSummary], total: &MarkerCounts) -> Table {
+    let count = |m: &MarkerCounts, tag: &String| m.get(tag).copied().unwrap_or(0).to_string();
+    let mut builder = Builder::default();
+    builder.push_record(std::iter::once("Language".to_string()).chain(tags.iter().cloned()));
+    for lang in langs {
+        builder.push_record(
+            std::iter::once(lang.language.name().to_string())
+                .chain(tags.iter().map(|t| count(&lang.totals.markers, t))),
+        );
+    }
+    builder.push_record(
+        std::iter::once("Total".to_string()).chain(tags.iter().map(|t| count(total, t))),
+    );
+    builder.build()
+}
+
+fn marker_line(markers: &MarkerCounts) -> String {
+    markers
+        .iter()
+        .map(|(tag, n)| format!("{} {}", tag, n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn percent(part: u64, whole: u64) -> String {
+    if whole == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}", part as f64 * 100.0 / whole as f64)
+}
+
 impl Reporter for TableReporter {
-    fn report(&self, report: &Report) -> Result<()> {
+    fn report(&self, report: &Report, out: &mut dyn Write) -> Result<()> {
         // By language table
         let rows: Vec<LangRow> = report.by_language.iter().map(LangRow::from).collect();
         let table = Table::new(rows);
-        println!("{}", table);
+        writeln!(out, "{}", table)?;
 
         // Total row
-        let total_row = LangRow::from(&report.total);
-        println!(
-            "\nTotal: {} files, {} lines ({} code, {} blank, {} comment)",
-            total_row.files, total_row.total, total_row.code, total_row.blank, total_row.comment
-        );
+        let total = &report.total;
+        writeln!(
+            out,
+            "\nTotal: {} files, {} lines ({} code, {} blank, {} comment; code: {} prod, {} test)",
+            total.files,
+            total.total_lines,
+            total.code_lines,
+            total.blank_lines,
+            total.comment_lines,
+            total.production_code_lines,
+            total.test_code_lines
+        )?;
+
+        if total.cells > 0 {
+            writeln!(
+                out,
+                "Notebook cells: {} (code and markdown); {} documentation lines",
+                total.cells, total.doc_lines
+            )?;
+        }
+
+        if let Some(dup) = &report.duplicates {
+            writeln!(
+                out,
+                "Duplicates skipped: {} files, {} lines ({} code, {} blank, {} comment)",
+                dup.files, dup.total_lines, dup.code_lines, dup.blank_lines, dup.comment_lines
+            )?;
+        }
+
+        if report.meta.total_only {
+            writeln!(
+                out,
+                "(--total-only: comments not classified; Code counts every non-blank line)"
+            )?;
+        }
+
+        // Hygiene table (file counts except for the line columns)
+        if report.meta.hygiene {
+            let mut rows: Vec<HygieneRow> = report
+                .by_language
+                .iter()
+                .map(|l| HygieneRow::new(l.language.name(), &l.totals.hygiene))
+                .collect();
+            rows.push(HygieneRow::new("Total", &total.hygiene));
+            writeln!(out, "\nHygiene:\n{}", Table::new(rows))?;
+        }
+
+        // Symbol table
+        if report.meta.symbols {
+            let mut rows: Vec<SymbolRow> = report
+                .by_language
+                .iter()
+                .map(|l| SymbolRow::new(l.language.name(), &l.totals.symbols))
+                .collect();
+            rows.push(SymbolRow::new("Total", &total.symbols));
+            writeln!(out, "\nSymbols:\n{}", Table::new(rows))?;
+        }
+
+        // Marker table
+        if !report.meta.markers.is_empty() {
+            writeln!(
+                out,
+                "\nMarkers:\n{}",
+                marker_table(&report.meta.markers, &report.by_language, &total.markers)
+            )?;
+            for hit in &report.marker_hits {
+                writeln!(out, "{}:{}: {}", hit.path.display(), hit.line, hit.text)?;
+            }
+        }
+
+        // Text statistics table
+        if self.text_stats {
+            let rows: Vec<TextRow> = report.by_language.iter().map(TextRow::from).collect();
+            writeln!(out, "\n{}", Table::new(rows))?;
+            writeln!(
+                out,
+                "Total: {} bytes, {} chars, max line {}, mean line {:.1}, {} lines over {} chars",
+                total.text.bytes,
+                total.text.chars,
+                total.text.max_line_length,
+                total.text.mean_line_length,
+                total.text.long_lines,
+                report.meta.long_line_width
+            )?;
+        }
+
+        // Churn table
+        if let Some(churn) = &report.churn {
+            let mut rows: Vec<ChurnRow> = churn
+                .by_language
+                .iter()
+                .map(|l| ChurnRow::new(l.language.name(), l.files, &l.churn))
+                .collect();
+            rows.push(ChurnRow::new("Total", churn.files, &churn.total));
+            writeln!(out, "\nChurn since {}:\n{}", churn.base, Table::new(rows))?;
+        }
+
+        // Baseline table
+        if let Some(check) = &report.baseline {
+            writeln!(
+                out,
+                "\nBaseline {}: {} rules, {} regressed{}",
+                check.file.display(),
+                check.rules.len(),
+                check.regressed,
+                if check.updated { " (updated)" } else { "" }
+            )?;
+            let rows: Vec<BaselineRow> = check.rules.iter().map(BaselineRow::from).collect();
+            writeln!(out, "{}", Table::new(rows))?;
+        }
+
+        // Estimation footer
+        if let Some(e) = &report.estimate {
+            writeln!(
+                out,
+                "\nEstimated Cost to Develop ({}): ${:.0}",
+                e.mode.label(),
+                e.cost
+            )?;
+            writeln!(
+                out,
+                "Estimated Schedule Effort: {:.2} months",
+                e.schedule_months
+            )?;
+            writeln!(out, "Estimated People Required: {:.2}", e.people)?;
+            writeln!(
+                out,
+                "Estimated Effort: {:.2} person-months (salary {:.0}, overhead {:.2})",
+                e.effort_person_months, e.salary, e.overhead
+            )?;
+        }
+
+        // By root tables
+        for root in &report.by_root {
+            writeln!(out, "\nRoot: {}", root.root.display())?;
+            let rows: Vec<LangRow> = root.by_language.iter().map(LangRow::from).collect();
+            writeln!(out, "{}", Table::new(rows))?;
+            writeln!(
+                out,
+                "Subtotal: {} files, {} lines ({} code, {} blank, {} comment)",
+                root.total.files,
+                root.total.total_lines,
+                root.total.code_lines,
+                root.total.blank_lines,
+                root.total.comment_lines
+            )?;
+        }
+
+        // By package tables
+        for pkg in &report.by_package {
+            match &pkg.package {
+                Some(p) => writeln!(
+                    out,
+                    "\nPackage: {} ({:?}{}) {}",
+                    p.name,
+                    p.kind,
+                    if p.workspace { " workspace" } else { "" },
+                    p.path.display()
+                )?,
+                None => writeln!(out, "\nPackage: (none)")?,
+            }
+            let rows: Vec<LangRow> = pkg.by_language.iter().map(LangRow::from).collect();
+            writeln!(out, "{}", Table::new(rows))?;
+        }
+
+        // By owner tables
+        for owner in &report.by_owner {
+            writeln!(out, "\nOwner: {}", owner.owner)?;
+            let rows: Vec<LangRow> = owner.by_language.iter().map(LangRow::from).collect();
+            writeln!(out, "{}", Table::new(rows))?;
+        }
+        if !report.unowned_files.is_empty() {
+            writeln!(out, "\nUnowned files ({}):", report.unowned_files.len())?;
+            for path in &report.unowned_files {
+                writeln!(out, "  {}", path.display())?;
+            }
+        }
+
+        // By license tables
+        for license in &report.by_license {
+            writeln!(
+                out,
+                "\nLicense: {} ({} files)",
+                license.license, license.total.files
+            )?;
+            let rows: Vec<LangRow> = license.by_language.iter().map(LangRow::from).collect();
+            writeln!(out, "{}", Table::new(rows))?;
+        }
+        if !report.unlicensed_files.is_empty() {
+            writeln!(
+                out,
+                "\nFiles without license header ({}):",
+                report.unlicensed_files.len()
+            )?;
+            for path in &report.unlicensed_files {
+                writeln!(out, "  {}", path.display())?;
+            }
+        }
 
         // By directory tables
         if !report.by_directory.is_empty() {
             for dir in &report.by_directory {
-                println!("\nDirectory: {}", dir.path.display());
-                let mut dir_rows: Vec<LangRow> = dir
-                    .by_language
-                    .values()
-                    .map(LangRow::from)
-                    .collect();
-                dir_rows.sort_by(|a, b| b.total.cmp(&a.total));
+                writeln!(out, "\nDirectory: {}", dir.path.display())?;
+                let mut dir_rows: Vec<LangRow> =
+                    dir.by_language.values().map(LangRow::from).collect();
+                dir_rows.sort_by_key(|r| std::cmp::Reverse(r.total));
                 let dir_table = Table::new(dir_rows);
-                println!("{}", dir_table);
+                writeln!(out, "{}", dir_table)?;
+
+                let mut markers = MarkerCounts::new();
+                for lang in dir.by_language.values() {
+                    crate::marker::merge_counts(&mut markers, &lang.totals.markers);
+                }
+                if !markers.is_empty() {
+                    writeln!(out, "Markers: {}", marker_line(&markers))?;
+                }
             }
         }
 
+        // By file table
+        if !report.by_file.is_empty() {
+            let rows: Vec<FileRow> = report.by_file.iter().map(FileRow::from).collect();
+            writeln!(out, "\n{}", Table::new(rows))?;
+        }
+
         Ok(())
     }
 }
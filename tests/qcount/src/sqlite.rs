@@ -0,0 +1,155 @@
+use crate::aggregator::{Accumulator, DirSummary, LangSummary, Meta, Report};
+use crate::counter::FileStats;
+use anyhow::{Context, Result};
+use rusqlite::{Connection, Transaction, params};
+use std::path::Path;
+
+/// Tables are created on first use; every export appends one run.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp     TEXT NOT NULL,
+    label         TEXT,
+    tool_version  TEXT NOT NULL,
+    roots         TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS files (
+    run_id          INTEGER NOT NULL REFERENCES runs(id),
+    path            TEXT NOT NULL,
+    language        TEXT NOT NULL,
+    total_lines     INTEGER NOT NULL,
+    code_lines      INTEGER NOT NULL,
+    blank_lines     INTEGER NOT NULL,
+    comment_lines   INTEGER NOT NULL,
+    test_code_lines INTEGER NOT NULL,
+    bytes           INTEGER NOT NULL,
+    license         TEXT
+);
+CREATE TABLE IF NOT EXISTS languages (
+    run_id          INTEGER NOT NULL REFERENCES runs(id),
+    language        TEXT NOT NULL,
+    files           INTEGER NOT NULL,
+    total_lines     INTEGER NOT NULL,
+    code_lines      INTEGER NOT NULL,
+    blank_lines     INTEGER NOT NULL,
+    comment_lines   INTEGER NOT NULL,
+    test_code_lines INTEGER NOT NULL,
+    bytes           INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS directories (
+    run_id          INTEGER NOT NULL REFERENCES runs(id),
+    path            TEXT NOT NULL,
+    language        TEXT NOT NULL,
+    files           INTEGER NOT NULL,
+    total_lines     INTEGER NOT NULL,
+    code_lines      INTEGER NOT NULL,
+    blank_lines     INTEGER NOT NULL,
+    comment_lines   INTEGER NOT NULL,
+    test_code_lines INTEGER NOT NULL,
+    bytes           INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS files_run ON files(run_id);
+CREATE INDEX IF NOT EXISTS languages_run ON languages(run_id);
+CREATE INDEX IF NOT EXISTS directories_run ON directories(run_id);
+";
+
+/// Appends one run to the database at `db` (created if missing) and returns
+/// its run id. `files` must hold every counted file.
+pub fn export(db: &Path, report: &Report, files: &[FileStats], label: Option<&str>) -> Result<i64> {
+    let mut conn = Connection::open(db).with_context(|| format!("cannot open {}", db.display()))?;
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    let run_id = insert_run(&tx, &report.meta, label)?;
+    {
+        let mut stmt =
+            tx.prepare("INSERT INTO files VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)")?;
+        for fs in files {
+            stmt.execute(params![
+                run_id,
+                fs.path.display().to_string(),
+                fs.language.name(),
+                fs.total_lines,
+                fs.code_lines,
+                fs.blank_lines,
+                fs.comment_lines,
+                fs.test_code_lines,
+                fs.text.bytes,
+                fs.license,
+            ])?;
+        }
+    }
+    for lang in &report.by_language {
+        insert_language(&tx, run_id, lang)?;
+    }
+    // Directory aggregates are rebuilt from the files so they are stored
+    // whether or not --by-dir was given.
+    for dir in directories(files) {
+        let path = dir.path.display().to_string();
+        for lang in dir.by_language.values() {
+            insert_directory(&tx, run_id, &path, lang)?;
+        }
+    }
+    tx.commit()?;
+    Ok(run_id)
+}
+
+fn insert_run(tx: &Transaction, meta: &Meta, label: Option<&str>) -> Result<i64> {
+    tx.execute(
+        "INSERT INTO runs (timestamp, label, tool_version, roots) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            meta.timestamp,
+            label,
+            meta.tool_version,
+            serde_json::to_string(&meta.roots)?,
+        ],
+    )?;
+    Ok(tx.last_insert_rowid())
+}
+
+fn insert_language(tx: &Transaction, run_id: i64, lang: &LangSummary) -> Result<()> {
+    let t = &lang.totals;
+    tx.execute(
+        "INSERT INTO languages VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            run_id,
+            lang.language.name(),
+            t.files,
+            t.total_lines,
+            t.code_lines,
+            t.blank_lines,
+            t.comment_lines,
+            t.test_code_lines,
+            t.text.bytes,
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_directory(tx: &Transaction, run_id: i64, path: &str, lang: &LangSummary) -> Result<()> {
+    let t = &lang.totals;
+    tx.execute(
+        "INSERT INTO directories VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            run_id,
+            path,
+            lang.language.name(),
+            t.files,
+            t.total_lines,
+            t.code_lines,
+            t.blank_lines,
+            t.comment_lines,
+            t.test_code_lines,
+            t.text.bytes,
+        ],
+    )?;
+    Ok(())
+}
+
+fn directories(files: &[FileStats]) -> Vec<DirSummary> {
+    let mut acc = Accumulator::new(true, &[]);
+    for fs in files {
+        acc.add(fs);
+    }
+    acc.finish(Meta::default()).by_directory
+}
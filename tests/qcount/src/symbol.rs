@@ -0,0 +1,208 @@
+use crate::language::{LANGUAGE_DEFS, Language};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Declarations found in the code lines of a file or a group of files.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct Symbols {
+    /// Functions and methods
+    pub functions: u64,
+    /// Structs, classes, enums, interfaces and traits
+    pub types: u64,
+    /// Modules, packages and namespaces
+    pub modules: u64,
+}
+
+impl Symbols {
+    pub fn merge(&mut self, other: &Symbols) {
+        self.functions += other.functions;
+        self.types += other.types;
+        self.modules += other.modules;
+    }
+}
+
+/// Leading words of statements that look like declarations to the C-style
+/// patterns (`else if (x) {`, `return foo(x);`) but never are.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "await", "case", "catch", "delete", "do", "else", "for", "foreach", "goto", "if", "new",
+    "return", "sizeof", "switch", "throw", "while", "with", "yield",
+];
+
+/// The `SymbolPatterns` of one language, compiled into one regex per kind.
+pub struct Matcher {
+    functions: Option<Regex>,
+    types: Option<Regex>,
+    modules: Option<Regex>,
+}
+
+static MATCHERS: LazyLock<HashMap<Language, Matcher>> = LazyLock::new(|| {
+    LANGUAGE_DEFS
+        .iter()
+        .map(|(lang, def)| {
+            let matcher = Matcher {
+                functions: compile(def.symbols.functions),
+                types: compile(def.symbols.types),
+                modules: compile(def.symbols.modules),
+            };
+            (lang.clone(), matcher)
+        })
+        .collect()
+});
+
+fn compile(patterns: &[&str]) -> Option<Regex> {
+    if patterns.is_empty() {
+        return None;
+    }
+    let alternation = format!("^(?:{})", patterns.join("|"));
+    Some(Regex::new(&alternation).expect("built-in symbol patterns are valid"))
+}
+
+/// Compiled declaration patterns of `language`, or None if it has none.
+pub fn matcher(language: &Language) -> Option<&'static Matcher> {
+    MATCHERS
+        .get(language)
+        .filter(|m| m.functions.is_some() || m.types.is_some() || m.modules.is_some())
+}
+
+impl Matcher {
+    /// Counts the declarations on one trimmed code line.
+    pub fn code_line(&self, trimmed: &str, symbols: &mut Symbols) {
+        let first_word = trimmed
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or("");
+        if STATEMENT_KEYWORDS.contains(&first_word) {
+            return;
+        }
+        let matches = |re: &Option<Regex>| re.as_ref().is_some_and(|re| re.is_match(trimmed));
+        if matches(&self.functions) {
+            symbols.functions += 1;
+        }
+        if matches(&self.types) {
+            symbols.types += 1;
+        }
+        if matches(&self.modules) {
+            symbols.modules += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(language: Language, source: &str) -> Symbols {
+        let matcher = matcher(&language).unwrap();
+        let mut symbols = Symbols::default();
+        for line in source.lines() {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() {
+                matcher.code_line(trimmed, &mut symbols);
+            }
+        }
+        symbols
+    }
+
+    fn symbols(functions: u64, types: u64, modules: u64) -> Symbols {
+        Symbols {
+            functions,
+            types,
+            modules,
+        }
+    }
+
+    #[test]
+    fn test_builtin_patterns_compile() {
+        for (lang, _) in LANGUAGE_DEFS {
+            let _ = matcher(lang);
+        }
+        assert!(matcher(&Language::Yaml).is_none());
+    }
+
+    #[test]
+    fn test_rust() {
+        let source = r#"
+pub mod walker;
+mod tests {
+pub(crate) struct Node {
+enum Kind { A, B }
+pub trait Reporter {
+impl Node {
+    pub const fn new() -> Self {
+    async unsafe fn run(&self) {
+extern "C" fn callback() {}
+let f = |x| x + 1;
+"#;
+        assert_eq!(count(Language::Rust, source), symbols(3, 3, 2));
+    }
+
+    #[test]
+    fn test_python() {
+        let source =
+            "class Parser:\n    def parse(self):\n    async def fetch():\nresult = define(x)\n";
+        assert_eq!(count(Language::Python, source), symbols(2, 1, 0));
+    }
+
+    #[test]
+    fn test_go() {
+        let source = "package main\ntype Walker struct {\ntype ID int\nfunc main() {\nfunc (w *Walker) Walk() error {\nfuncs := 1\n";
+        assert_eq!(count(Language::Go, source), symbols(2, 1, 1));
+    }
+
+    #[test]
+    fn test_typescript() {
+        let source = r#"
+export interface Options {
+export default class App {
+enum Color { Red }
+namespace util {
+export async function load() {
+const add = (a, b) => a + b;
+  constructor(opts: Options) {
+  render(): string {
+if (ready) {
+foo(function () {
+"#;
+        assert_eq!(count(Language::TypeScript, source), symbols(4, 3, 1));
+    }
+
+    #[test]
+    fn test_c_style_definitions_not_calls() {
+        let source = r#"
+static int parse(const char *s) {
+struct node *make_node(void)
+int prototype(int x);
+} else if (x) {
+else if (y) {
+return compute(a, b);
+printf("%d\n", x);
+size_t n = strlen(s);
+typedef struct {
+struct node {
+"#;
+        assert_eq!(count(Language::C, source), symbols(2, 2, 0));
+    }
+
+    #[test]
+    fn test_cpp() {
+        let source = "namespace qcount {\nclass Walker;\ntemplate <typename T> class Box {\nenum class Mode {\nstd::vector<int> Walker::roots() const {\n";
+        assert_eq!(count(Language::Cpp, source), symbols(1, 2, 1));
+    }
+
+    #[test]
+    fn test_java() {
+        let source = "package com.example.app;\npublic final class Main {\n    public static void main(String[] args) {\n    List<String> names() {\n        return build(x);\n        Foo foo = new Foo(1);\n";
+        assert_eq!(count(Language::Java, source), symbols(2, 1, 1));
+    }
+
+    #[test]
+    fn test_ruby_and_shell() {
+        let ruby = "module Qcount\n  class Walker\n    def walk(root)\n    class << self\n";
+        assert_eq!(count(Language::Ruby, ruby), symbols(1, 1, 1));
+        let shell = "function usage {\ncleanup() {\nmain \"$@\"\n";
+        assert_eq!(count(Language::Shell, shell), symbols(2, 0, 0));
+    }
+}
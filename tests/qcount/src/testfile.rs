@@ -0,0 +1,54 @@
+use std::path::Path;
+
+/// Directory names whose contents are treated as test code.
+const TEST_DIRS: &[&str] = &["tests", "test", "__tests__", "spec"];
+
+/// File name suffixes (before the extension) that mark a test file.
+const TEST_SUFFIXES: &[&str] = &["_test", "_tests", "_spec", ".test", ".spec"];
+
+/// File name prefixes that mark a test file.
+const TEST_PREFIXES: &[&str] = &["test_"];
+
+/// Returns true if the path looks like a test file by naming convention
+/// (`*_test.go`, `test_*.py`, `*.spec.ts`, files under `tests/`, ...).
+pub fn is_test_path(path: &Path) -> bool {
+    if let Some(parent) = path.parent() {
+        let in_test_dir = parent
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .any(|c| TEST_DIRS.contains(&c));
+        if in_test_dir {
+            return true;
+        }
+    }
+
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    TEST_PREFIXES.iter().any(|p| stem.starts_with(p))
+        || TEST_SUFFIXES.iter().any(|s| stem.ends_with(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming_conventions() {
+        assert!(is_test_path(Path::new("pkg/server_test.go")));
+        assert!(is_test_path(Path::new("app/test_models.py")));
+        assert!(is_test_path(Path::new("web/button.spec.ts")));
+        assert!(is_test_path(Path::new("web/button.test.js")));
+        assert!(is_test_path(Path::new("tests/integration/count_test.rs")));
+        assert!(is_test_path(Path::new("crate/tests/helpers.rs")));
+        assert!(is_test_path(Path::new("web/__tests__/app.js")));
+    }
+
+    #[test]
+    fn test_production_paths() {
+        assert!(!is_test_path(Path::new("src/main.rs")));
+        assert!(!is_test_path(Path::new("src/testing.rs")));
+        assert!(!is_test_path(Path::new("pkg/contest.go")));
+        assert!(!is_test_path(Path::new("latest/app.py")));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::aggregator::{LangMap, LangSummary, Totals, add_to_langs, sorted_langs};
+use crate::counter::FileStats;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A directory or file of the counted tree, with totals of everything below it.
+#[derive(Debug)]
+pub struct Node {
+    /// Last path component (the full root path for root nodes)
+    pub name: String,
+    pub path: PathBuf,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Set for file nodes
+    pub file: Option<FileStats>,
+    pub total: Totals,
+    langs: LangMap,
+}
+
+impl Node {
+    fn new(name: String, path: PathBuf, parent: Option<usize>) -> Self {
+        Node {
+            name,
+            path,
+            parent,
+            children: Vec::new(),
+            file: None,
+            total: Totals::default(),
+            langs: LangMap::new(),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file.is_none()
+    }
+
+    pub fn by_language(&self) -> Vec<LangSummary> {
+        sorted_langs(self.langs.clone())
+    }
+}
+
+/// Full directory tree of a run, kept in an arena; node 0 is the top.
+/// With several roots the top is a synthetic node whose children are the roots.
+#[derive(Debug)]
+pub struct DirTree {
+    nodes: Vec<Node>,
+}
+
+impl DirTree {
+    /// Builds the tree from every counted file. A file belongs to the first
+    /// root containing it, as in `Accumulator`.
+    pub fn build(roots: &[PathBuf], files: &[FileStats]) -> Self {
+        let mut tree = DirTree { nodes: Vec::new() };
+        let mut dirs: HashMap<PathBuf, usize> = HashMap::new();
+
+        let root_ids: Vec<usize> = if roots.len() == 1 {
+            let root = &roots[0];
+            tree.push(Node::new(root.display().to_string(), root.clone(), None));
+            vec![0]
+        } else {
+            tree.push(Node::new("(roots)".to_string(), PathBuf::new(), None));
+            roots
+                .iter()
+                .map(|root| {
+                    let node = Node::new(root.display().to_string(), root.clone(), Some(0));
+                    tree.add_child(0, node)
+                })
+                .collect()
+        };
+        for (root, &id) in roots.iter().zip(&root_ids) {
+            dirs.insert(root.clone(), id);
+        }
+
+        for fs in files {
+            let Some(i) = roots.iter().position(|r| fs.path.starts_with(r)) else {
+                continue;
+            };
+            let relative = fs.path.strip_prefix(&roots[i]).unwrap_or(&fs.path);
+            let mut dir = root_ids[i];
+            let mut dir_path = roots[i].clone();
+            if let Some(parent) = relative.parent() {
+                for component in parent.components() {
+                    dir_path.push(component);
+                    dir = match dirs.get(&dir_path) {
+                        Some(&id) => id,
+                        None => {
+                            let name = component.as_os_str().to_string_lossy().into_owned();
+                            let node = Node::new(name, dir_path.clone(), Some(dir));
+                            let id = tree.add_child(dir, node);
+                            dirs.insert(dir_path.clone(), id);
+                            id
+                        }
+                    };
+                }
+            }
+
+            let mut node = Node::new(file_name(&fs.path), fs.path.clone(), Some(dir));
+            node.file = Some(fs.clone());
+            let id = tree.add_child(dir, node);
+            tree.add_stats(id, fs);
+        }
+        tree
+    }
+
+    fn push(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn add_child(&mut self, parent: usize, node: Node) -> usize {
+        let id = self.push(node);
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    /// Adds a file's counts to the node and every ancestor.
+    fn add_stats(&mut self, id: usize, fs: &FileStats) {
+        let mut current = Some(id);
+        while let Some(i) = current {
+            let node = &mut self.nodes[i];
+            node.total.add(fs);
+            add_to_langs(&mut node.langs, fs);
+            current = node.parent;
+        }
+    }
+
+    pub fn node(&self, id: usize) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::{CountOptions, count_content};
+    use crate::language::Language;
+
+    fn file(path: &str, content: &str, language: Language) -> FileStats {
+        count_content(content, Path::new(path), language, &CountOptions::default())
+    }
+
+    fn sample() -> DirTree {
+        let files = vec![
+            file("repo/src/main.rs", "fn main() {}\n// c\n", Language::Rust),
+            file("repo/src/util/mod.rs", "fn a() {}\n\n", Language::Rust),
+            file("repo/app.py", "x = 1\n", Language::Python),
+        ];
+        DirTree::build(&[PathBuf::from("repo")], &files)
+    }
+
+    #[test]
+    fn test_totals_roll_up() {
+        let tree = sample();
+        let root = tree.node(0);
+        assert_eq!(root.name, "repo");
+        assert_eq!(root.total.files, 3);
+        assert_eq!(root.total.code_lines, 3);
+        assert_eq!(root.by_language().len(), 2);
+
+        let src = root
+            .children
+            .iter()
+            .map(|&id| tree.node(id))
+            .find(|n| n.name == "src")
+            .unwrap();
+        assert!(src.is_dir());
+        assert_eq!(src.total.files, 2);
+        assert_eq!(src.total.comment_lines, 1);
+        assert_eq!(src.children.len(), 2);
+    }
+
+    #[test]
+    fn test_multiple_roots() {
+        let files = vec![
+            file("a/x.rs", "x\n", Language::Rust),
+            file("b/y.rs", "y\n", Language::Rust),
+        ];
+        let tree = DirTree::build(&[PathBuf::from("a"), PathBuf::from("b")], &files);
+        assert_eq!(tree.node(0).children.len(), 2);
+        assert_eq!(tree.node(0).total.files, 2);
+        assert_eq!(tree.len(), 5);
+    }
+}
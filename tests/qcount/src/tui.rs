@@ -0,0 +1,435 @@
+use crate::tree::{DirTree, Node};
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashSet;
+
+/// Column the tree is sorted by (directories always come before files).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Files,
+    Code,
+    Comment,
+    Blank,
+    Total,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::Files,
+            SortKey::Files => SortKey::Code,
+            SortKey::Code => SortKey::Comment,
+            SortKey::Comment => SortKey::Blank,
+            SortKey::Blank => SortKey::Total,
+            SortKey::Total => SortKey::Name,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Files => "files",
+            SortKey::Code => "code",
+            SortKey::Comment => "comment",
+            SortKey::Blank => "blank",
+            SortKey::Total => "total",
+        }
+    }
+
+    fn value(self, node: &Node) -> u64 {
+        match self {
+            SortKey::Name => 0,
+            SortKey::Files => node.total.files,
+            SortKey::Code => node.total.code_lines,
+            SortKey::Comment => node.total.comment_lines,
+            SortKey::Blank => node.total.blank_lines,
+            SortKey::Total => node.total.total_lines,
+        }
+    }
+}
+
+/// Browser state, independent of the terminal so it can be driven in tests.
+pub struct App {
+    tree: DirTree,
+    expanded: HashSet<usize>,
+    /// Visible rows as (node id, depth), rebuilt after every change
+    rows: Vec<(usize, usize)>,
+    table: TableState,
+    sort: SortKey,
+    descending: bool,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(tree: DirTree) -> Self {
+        let mut app = App {
+            tree,
+            expanded: HashSet::from([0]),
+            rows: Vec::new(),
+            table: TableState::default().with_selected(Some(0)),
+            sort: SortKey::Code,
+            descending: true,
+            quit: false,
+        };
+        app.refresh();
+        app
+    }
+
+    fn selected(&self) -> usize {
+        let row = self.table.selected().unwrap_or(0);
+        self.rows.get(row).map_or(0, |&(id, _)| id)
+    }
+
+    fn sorted_children(&self, id: usize) -> Vec<usize> {
+        let mut children = self.tree.node(id).children.clone();
+        children.sort_by(|&a, &b| {
+            let (a, b) = (self.tree.node(a), self.tree.node(b));
+            let order = match self.sort {
+                SortKey::Name => a.name.cmp(&b.name),
+                key => key.value(a).cmp(&key.value(b)),
+            };
+            let order = if self.descending && self.sort != SortKey::Name {
+                order.reverse()
+            } else {
+                order
+            };
+            b.is_dir().cmp(&a.is_dir()).then(order)
+        });
+        children
+    }
+
+    /// Rebuilds the visible rows, keeping the selected node selected.
+    fn refresh(&mut self) {
+        let selected = if self.rows.is_empty() {
+            0
+        } else {
+            self.selected()
+        };
+        self.rows.clear();
+        if self.tree.is_empty() {
+            return;
+        }
+        let mut stack = vec![(0, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            self.rows.push((id, depth));
+            if self.expanded.contains(&id) {
+                for child in self.sorted_children(id).into_iter().rev() {
+                    stack.push((child, depth + 1));
+                }
+            }
+        }
+        let row = self.rows.iter().position(|&(id, _)| id == selected);
+        self.table.select(Some(row.unwrap_or(0)));
+    }
+
+    fn select_row(&mut self, row: usize) {
+        let last = self.rows.len().saturating_sub(1);
+        self.table.select(Some(row.min(last)));
+    }
+
+    pub fn on_key(&mut self, code: KeyCode) {
+        let row = self.table.selected().unwrap_or(0);
+        let id = self.selected();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.select_row(row + 1),
+            KeyCode::Up | KeyCode::Char('k') => self.select_row(row.saturating_sub(1)),
+            KeyCode::PageDown => self.select_row(row + 20),
+            KeyCode::PageUp => self.select_row(row.saturating_sub(20)),
+            KeyCode::Home | KeyCode::Char('g') => self.select_row(0),
+            KeyCode::End | KeyCode::Char('G') => self.select_row(usize::MAX),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => {
+                if !self.tree.node(id).is_dir() {
+                    return;
+                }
+                if self.expanded.insert(id) {
+                    self.refresh();
+                } else {
+                    // Already open: step into the first child
+                    self.select_row(row + 1);
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                if self.expanded.remove(&id) {
+                    self.refresh();
+                } else if let Some(parent) = self.tree.node(id).parent
+                    && let Some(parent_row) = self.rows.iter().position(|&(n, _)| n == parent)
+                {
+                    self.select_row(parent_row);
+                }
+            }
+            KeyCode::Char(' ') if self.tree.node(id).is_dir() => {
+                if !self.expanded.remove(&id) {
+                    self.expanded.insert(id);
+                }
+                self.refresh();
+            }
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.refresh();
+            }
+            KeyCode::Char('r') => {
+                self.descending = !self.descending;
+                self.refresh();
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list, detail] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(body);
+
+        let order = if self.descending && self.sort != SortKey::Name {
+            "desc"
+        } else {
+            "asc"
+        };
+        frame.render_widget(
+            Paragraph::new(format!(
+                "qcount {}  sort: {} ({})",
+                self.tree.node(0).name,
+                self.sort.label(),
+                order
+            )),
+            header,
+        );
+        self.draw_tree(frame, list);
+        self.draw_detail(frame, detail);
+        frame.render_widget(
+            Paragraph::new(
+                "↑↓ move  → expand  ← collapse  space toggle  s sort  r reverse  q quit",
+            ),
+            footer,
+        );
+    }
+
+    fn draw_tree(&mut self, frame: &mut Frame, area: Rect) {
+        let rows: Vec<Row> = self
+            .rows
+            .iter()
+            .map(|&(id, depth)| {
+                let node = self.tree.node(id);
+                let marker = match (node.is_dir(), self.expanded.contains(&id)) {
+                    (false, _) => "  ",
+                    (true, true) => "▾ ",
+                    (true, false) => "▸ ",
+                };
+                Row::new([
+                    format!("{}{}{}", "  ".repeat(depth), marker, node.name),
+                    node.total.files.to_string(),
+                    node.total.code_lines.to_string(),
+                    node.total.comment_lines.to_string(),
+                    node.total.blank_lines.to_string(),
+                    node.total.total_lines.to_string(),
+                ])
+            })
+            .collect();
+        let widths = [
+            Constraint::Min(20),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Length(9),
+        ];
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(["Name", "Files", "Code", "Comment", "Blank", "Total"])
+                    .style(Style::new().add_modifier(Modifier::BOLD)),
+            )
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .block(Block::new().borders(Borders::ALL).title("Tree"));
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+        let node = self.tree.node(self.selected());
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .title(node.path.display().to_string());
+
+        if let Some(fs) = &node.file {
+            let lines: Vec<Line> = [
+                ("Language", fs.language.name().to_string()),
+                ("Total", fs.total_lines.to_string()),
+                ("Code", fs.code_lines.to_string()),
+                ("  production", fs.production_code_lines.to_string()),
+                ("  test", fs.test_code_lines.to_string()),
+                ("Comment", fs.comment_lines.to_string()),
+                ("Blank", fs.blank_lines.to_string()),
+                ("Bytes", fs.text.bytes.to_string()),
+                ("Max line", fs.text.max_line_length.to_string()),
+                ("Mean line", format!("{:.1}", fs.text.mean_line_length)),
+                ("Long lines", fs.text.long_lines.to_string()),
+            ]
+            .into_iter()
+            .map(|(k, v)| Line::from(format!("{:<13}{}", k, v)))
+            .collect();
+            frame.render_widget(Paragraph::new(lines).block(block), area);
+            return;
+        }
+
+        let rows: Vec<Row> = node
+            .by_language()
+            .iter()
+            .map(|l| {
+                Row::new([
+                    l.language.name().to_string(),
+                    l.totals.files.to_string(),
+                    l.totals.code_lines.to_string(),
+                    l.totals.comment_lines.to_string(),
+                    l.totals.blank_lines.to_string(),
+                ])
+            })
+            .collect();
+        let widths = [
+            Constraint::Min(10),
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(7),
+        ];
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(["Language", "Files", "Code", "Comment", "Blank"])
+                    .style(Style::new().add_modifier(Modifier::BOLD)),
+            )
+            .block(block);
+        frame.render_widget(table, area);
+    }
+}
+
+/// Runs the browser full-screen until the user quits.
+pub fn run(tree: DirTree) -> Result<()> {
+    let mut terminal = match ratatui::try_init() {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            ratatui::restore();
+            return Err(anyhow::Error::new(e).context(
+                "failed to initialize the terminal; --tui needs an interactive terminal",
+            ));
+        }
+    };
+    let result = event_loop(&mut terminal, App::new(tree));
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, mut app: App) -> Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            app.on_key(key.code);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counter::{CountOptions, count_content};
+    use crate::language::Language;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use std::path::{Path, PathBuf};
+
+    fn app() -> App {
+        let file = |path: &str, content: &str| {
+            count_content(
+                content,
+                Path::new(path),
+                Language::Rust,
+                &CountOptions::default(),
+            )
+        };
+        let files = vec![
+            file("repo/src/lib.rs", "fn a() {}\nfn b() {}\n"),
+            file("repo/src/deep/x.rs", "fn x() {}\n"),
+            file("repo/build.rs", "fn main() {}\n// c\n// d\n"),
+            file("repo/z.rs", "fn z() {}\nfn y() {}\nfn w() {}\n"),
+        ];
+        App::new(DirTree::build(&[PathBuf::from("repo")], &files))
+    }
+
+    fn names(app: &App) -> Vec<&str> {
+        app.rows
+            .iter()
+            .map(|&(id, _)| app.tree.node(id).name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_collapse_and_navigation() {
+        let mut app = app();
+        // Root is open; directories come before files
+        assert_eq!(names(&app), vec!["repo", "src", "z.rs", "build.rs"]);
+
+        app.on_key(KeyCode::Down);
+        app.on_key(KeyCode::Right);
+        assert_eq!(
+            names(&app),
+            vec!["repo", "src", "deep", "lib.rs", "z.rs", "build.rs"]
+        );
+
+        // Left on a file jumps to its parent, then collapses it
+        app.on_key(KeyCode::Down);
+        app.on_key(KeyCode::Down);
+        app.on_key(KeyCode::Left);
+        assert_eq!(app.tree.node(app.selected()).name, "src");
+        app.on_key(KeyCode::Left);
+        assert_eq!(names(&app), vec!["repo", "src", "z.rs", "build.rs"]);
+
+        app.on_key(KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_sorting() {
+        let mut app = app();
+        // Code lines, descending
+        assert_eq!(names(&app)[2..], ["z.rs", "build.rs"]);
+        app.on_key(KeyCode::Char('r'));
+        assert_eq!(names(&app)[2..], ["build.rs", "z.rs"]);
+
+        // Comment lines, ascending; the selection follows its node
+        app.on_key(KeyCode::End);
+        app.on_key(KeyCode::Char('s'));
+        assert_eq!(app.sort, SortKey::Comment);
+        assert_eq!(names(&app)[2..], ["z.rs", "build.rs"]);
+        assert_eq!(app.tree.node(app.selected()).name, "z.rs");
+    }
+
+    #[test]
+    fn test_renders_tree_and_detail() {
+        let mut app = app();
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|c| c.symbol())
+            .collect();
+        assert!(screen.contains("▾ repo"));
+        assert!(screen.contains("▸ src"));
+        assert!(screen.contains("Rust"));
+    }
+}
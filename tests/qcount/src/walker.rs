@@ -1,18 +1,335 @@
-use ignore::{DirEntry, WalkBuilder};
-use std::path::Path;
+use anyhow::{Context, Result};
+use ignore::WalkBuilder;
+use ignore::overrides::{Override, OverrideBuilder};
+use std::path::{Component, Path, PathBuf};
 
-pub fn walk(root: &Path, excludes: &[String]) -> Vec<DirEntry> {
-    let mut builder = WalkBuilder::new(root);
-    builder.hidden(true).git_ignore(true);
-    builder.add_custom_ignore_filename(".qcountignore");
+/// Walker settings exposed on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Include hidden files and directories (dotfiles)
+    pub hidden: bool,
+    /// Ignore `.gitignore`, `.ignore` and `.qcountignore` files
+    pub no_ignore: bool,
+    pub follow_links: bool,
+    /// Do not cross file system boundaries
+    pub same_file_system: bool,
+    pub max_depth: Option<usize>,
+    /// Only walk files matching one of these globs (empty = all files)
+    pub includes: Vec<String>,
+    /// Skip files and directories matching these globs
+    pub excludes: Vec<String>,
+    /// Globs from `.qcount.toml`
+    pub config: Option<ConfigGlobs>,
+}
 
-    for pattern in excludes {
-        builder.add_ignore(pattern);
-    }
+/// Include and exclude globs from `.qcount.toml`. They are matched relative
+/// to the directory holding the file, whichever root is walked.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigGlobs {
+    pub dir: PathBuf,
+    /// Only walk files matching one of these globs (empty = all files)
+    pub includes: Vec<String>,
+    pub excludes: Vec<String>,
+}
 
+/// Builds a walker over every root. Include and exclude globs are matched
+/// relative to the first root (config globs relative to the config file),
+/// gitignore-style; includes never bring back files skipped by ignore files.
+pub fn builder(roots: &[PathBuf], options: &WalkOptions) -> Result<WalkBuilder> {
+    let mut builder = WalkBuilder::new(&roots[0]);
+    for root in &roots[1..] {
+        builder.add(root);
+    }
     builder
-        .build()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
-        .collect()
+        .hidden(!options.hidden)
+        .follow_links(options.follow_links)
+        .same_file_system(options.same_file_system)
+        .max_depth(options.max_depth);
+
+    if options.no_ignore {
+        builder
+            .ignore(false)
+            .git_ignore(false)
+            .git_global(false)
+            .git_exclude(false)
+            .parents(false);
+    } else {
+        builder.git_ignore(true);
+        builder.add_custom_ignore_filename(".qcountignore");
+    }
+
+    builder.overrides(exclude_overrides(
+        &roots[0],
+        &options.excludes,
+        "--exclude",
+    )?);
+
+    // Whitelist overrides would take precedence over ignore files, so
+    // includes are applied as a filter on top of the ignore rules instead.
+    let includes = include_overrides(&roots[0], &options.includes, "--include")?;
+    let config = match &options.config {
+        Some(globs) => {
+            let canonical = roots
+                .iter()
+                .map(|root| {
+                    let canonical = std::fs::canonicalize(root)
+                        .with_context(|| format!("cannot resolve {}", root.display()))?;
+                    Ok((root.clone(), canonical))
+                })
+                .collect::<Result<_>>()?;
+            Some(Anchored::new(globs, canonical)?)
+        }
+        None => None,
+    };
+    if includes.is_some() || config.is_some() {
+        builder.filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !includes
+                .as_ref()
+                .is_some_and(|i| i.matched(entry.path(), is_dir).is_ignore())
+                && config.as_ref().is_none_or(|c| c.keep(entry.path(), is_dir))
+        });
+    }
+
+    Ok(builder)
+}
+
+/// Applies `--hidden`, `--include` and `--exclude` to paths that do not
+/// come from the walker (e.g. files listed by git). A file is rejected when
+/// it or one of its parent directories below its root is hidden or
+/// excluded. Globs are matched relative to the first root, as in `builder`.
+/// `roots` must be canonical, like the paths passed to the filter.
+pub fn path_filter(
+    roots: &[PathBuf],
+    options: &WalkOptions,
+) -> Result<impl Fn(&Path) -> bool + use<>> {
+    let excludes = exclude_overrides(&roots[0], &options.excludes, "--exclude")?;
+    let includes = include_overrides(&roots[0], &options.includes, "--include")?;
+    let config = match &options.config {
+        Some(globs) => Some(Anchored::new(
+            globs,
+            roots.iter().map(|r| (r.clone(), r.clone())).collect(),
+        )?),
+        None => None,
+    };
+    let roots = roots.to_vec();
+    let hidden = options.hidden;
+    Ok(move |path: &Path| {
+        let Some(root) = roots.iter().find(|root| path.starts_with(root)) else {
+            return false;
+        };
+        if !hidden && is_hidden(path.strip_prefix(root).unwrap_or(path)) {
+            return false;
+        }
+        if includes
+            .as_ref()
+            .is_some_and(|i| i.matched(path, false).is_ignore())
+        {
+            return false;
+        }
+        let mut dirs = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(root) && dir != root);
+        !excludes.matched(path, false).is_ignore()
+            && config.as_ref().is_none_or(|c| c.keep(path, false))
+            && !dirs.any(|dir| {
+                excludes.matched(dir, true).is_ignore()
+                    || config.as_ref().is_some_and(|c| !c.keep(dir, true))
+            })
+    })
+}
+
+/// Whether any component of `relative` is a dotfile, as the walker's
+/// hidden-file filter sees it.
+fn is_hidden(relative: &Path) -> bool {
+    relative
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with('.') && c != Component::CurDir)
+}
+
+/// Config globs, matched against walked paths after re-rooting them from
+/// the root they were walked under onto its canonical path.
+struct Anchored {
+    includes: Option<Override>,
+    excludes: Override,
+    /// (root as walked, canonical root)
+    roots: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Anchored {
+    fn new(globs: &ConfigGlobs, roots: Vec<(PathBuf, PathBuf)>) -> Result<Anchored> {
+        Ok(Anchored {
+            includes: include_overrides(&globs.dir, &globs.includes, "include")?,
+            excludes: exclude_overrides(&globs.dir, &globs.excludes, "exclude")?,
+            roots,
+        })
+    }
+
+    fn keep(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(path) = self.roots.iter().find_map(|(root, canonical)| {
+            path.strip_prefix(root)
+                .ok()
+                .map(|rest| canonical.join(rest))
+        }) else {
+            return true;
+        };
+        !self.excludes.matched(&path, is_dir).is_ignore()
+            && !self
+                .includes
+                .as_ref()
+                .is_some_and(|i| i.matched(&path, is_dir).is_ignore())
+    }
+}
+
+fn exclude_overrides(root: &Path, patterns: &[String], name: &str) -> Result<Override> {
+    let mut excludes = OverrideBuilder::new(root);
+    for pattern in patterns {
+        excludes
+            .add(&format!("!{}", pattern))
+            .with_context(|| format!("invalid {} glob: {}", name, pattern))?;
+    }
+    Ok(excludes.build()?)
+}
+
+fn include_overrides(root: &Path, patterns: &[String], name: &str) -> Result<Option<Override>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut includes = OverrideBuilder::new(root);
+    for pattern in patterns {
+        includes
+            .add(pattern)
+            .with_context(|| format!("invalid {} glob: {}", name, pattern))?;
+    }
+    Ok(Some(includes.build()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn walk(root: &Path, options: &WalkOptions) -> Vec<String> {
+        let mut files: Vec<String> = builder(&[root.to_path_buf()], options)
+            .unwrap()
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_some_and(|t| t.is_file()))
+            .map(|e| {
+                let rel = e.path().strip_prefix(root).unwrap();
+                rel.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for rel in [
+            ".github/workflows/ci.yml",
+            "src/main.rs",
+            "src/deep/nested/mod.rs",
+            "vendor/lib.js",
+            "generated.rs",
+        ] {
+            let path = root.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "x\n").unwrap();
+        }
+        std::fs::write(root.join(".qcountignore"), "generated.rs\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_defaults_skip_hidden_and_ignored() {
+        let dir = fixture();
+        let files = walk(dir.path(), &WalkOptions::default());
+        assert_eq!(
+            files,
+            vec!["src/deep/nested/mod.rs", "src/main.rs", "vendor/lib.js"]
+        );
+    }
+
+    #[test]
+    fn test_hidden_and_no_ignore() {
+        let dir = fixture();
+        let options = WalkOptions {
+            hidden: true,
+            no_ignore: true,
+            ..WalkOptions::default()
+        };
+        let files = walk(dir.path(), &options);
+        assert!(files.contains(&".github/workflows/ci.yml".to_string()));
+        assert!(files.contains(&"generated.rs".to_string()));
+    }
+
+    #[test]
+    fn test_include_exclude_and_max_depth() {
+        let dir = fixture();
+        let options = WalkOptions {
+            includes: vec!["*.rs".into()],
+            excludes: vec!["deep/".into()],
+            ..WalkOptions::default()
+        };
+        assert_eq!(walk(dir.path(), &options), vec!["src/main.rs"]);
+
+        let options = WalkOptions {
+            max_depth: Some(1),
+            no_ignore: true,
+            ..WalkOptions::default()
+        };
+        assert_eq!(walk(dir.path(), &options), vec!["generated.rs"]);
+    }
+
+    #[test]
+    fn test_invalid_glob_is_an_error() {
+        let options = WalkOptions {
+            excludes: vec!["a{".into()],
+            ..WalkOptions::default()
+        };
+        assert!(builder(&[PathBuf::from(".")], &options).is_err());
+    }
+
+    #[test]
+    fn test_config_globs_are_relative_to_config_dir() {
+        let dir = fixture();
+        let root = dir.path().join("src");
+        let options = WalkOptions {
+            config: Some(ConfigGlobs {
+                dir: std::fs::canonicalize(dir.path()).unwrap(),
+                includes: Vec::new(),
+                excludes: vec!["src/deep".into()],
+            }),
+            ..WalkOptions::default()
+        };
+        assert_eq!(walk(&root, &options), vec!["main.rs"]);
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let options = WalkOptions {
+            includes: vec!["*.rs".into()],
+            excludes: vec!["vendor/".into()],
+            ..WalkOptions::default()
+        };
+        let keep = path_filter(&[PathBuf::from("/repo")], &options).unwrap();
+        assert!(keep(Path::new("/repo/src/main.rs")));
+        assert!(!keep(Path::new("/repo/src/app.js")));
+        assert!(!keep(Path::new("/repo/vendor/dep/lib.rs")));
+        assert!(!keep(Path::new("/repo/.github/build.rs")));
+        assert!(!keep(Path::new("/other/main.rs")));
+
+        let options = WalkOptions {
+            hidden: true,
+            ..WalkOptions::default()
+        };
+        let keep = path_filter(&[PathBuf::from("/home/.me/repo")], &options).unwrap();
+        assert!(keep(Path::new("/home/.me/repo/.github/build.rs")));
+        let keep =
+            path_filter(&[PathBuf::from("/home/.me/repo")], &WalkOptions::default()).unwrap();
+        assert!(keep(Path::new("/home/.me/repo/src/main.rs")));
+        assert!(!keep(Path::new("/home/.me/repo/.env.rs")));
+    }
 }
@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 fn fixtures_dir() -> PathBuf {
@@ -8,28 +9,35 @@ fn fixtures_dir() -> PathBuf {
         .join("fixtures")
 }
 
-fn run_qcount_json(path: &PathBuf) -> serde_json::Value {
+/// Runs qcount with `args`, asserting that it exits successfully; returns stdout.
+fn run_qcount<S: AsRef<OsStr>>(args: &[S]) -> Vec<u8> {
     let bin = env!("CARGO_BIN_EXE_qcount");
     let output = Command::new(bin)
-        .arg(path)
-        .arg("--json")
+        .args(args)
         .output()
         .expect("Failed to run qcount");
 
     assert!(
         output.status.success(),
-        "qcount exited with non-zero status: {:?}",
+        "qcount {:?} exited with non-zero status: {:?}",
+        args.iter().map(|a| a.as_ref()).collect::<Vec<_>>(),
         String::from_utf8_lossy(&output.stderr)
     );
+    output.stdout
+}
 
-    let stdout = String::from_utf8(output.stdout).expect("Invalid UTF-8 output");
+/// Runs qcount on `path` with `--json` plus `args` and parses the report.
+fn run_qcount_json(path: &Path, args: &[&str]) -> serde_json::Value {
+    let mut all = vec![path.as_os_str(), OsStr::new("--json")];
+    all.extend(args.iter().map(OsStr::new));
+    let stdout = String::from_utf8(run_qcount(&all)).expect("Invalid UTF-8 output");
     serde_json::from_str(&stdout).expect("Failed to parse JSON output")
 }
 
 #[test]
 fn test_fixture_total_files() {
     let fixtures = fixtures_dir();
-    let result = run_qcount_json(&fixtures);
+    let result = run_qcount_json(&fixtures, &[]);
 
     let total_files = result["total"]["files"].as_u64().unwrap();
     assert_eq!(total_files, 3, "Expected 3 fixture files");
@@ -38,7 +46,7 @@ fn test_fixture_total_files() {
 #[test]
 fn test_fixture_rust_stats() {
     let fixtures = fixtures_dir();
-    let result = run_qcount_json(&fixtures);
+    let result = run_qcount_json(&fixtures, &[]);
 
     let by_language = result["by_language"].as_array().unwrap();
     let rust = by_language
@@ -56,7 +64,7 @@ fn test_fixture_rust_stats() {
 #[test]
 fn test_fixture_python_stats() {
     let fixtures = fixtures_dir();
-    let result = run_qcount_json(&fixtures);
+    let result = run_qcount_json(&fixtures, &[]);
 
     let by_language = result["by_language"].as_array().unwrap();
     let python = by_language
@@ -74,7 +82,7 @@ fn test_fixture_python_stats() {
 #[test]
 fn test_fixture_javascript_stats() {
     let fixtures = fixtures_dir();
-    let result = run_qcount_json(&fixtures);
+    let result = run_qcount_json(&fixtures, &[]);
 
     let by_language = result["by_language"].as_array().unwrap();
     let js = by_language
@@ -92,9 +100,1001 @@ fn test_fixture_javascript_stats() {
 #[test]
 fn test_fixture_total_lines() {
     let fixtures = fixtures_dir();
-    let result = run_qcount_json(&fixtures);
+    let result = run_qcount_json(&fixtures, &[]);
 
     let total_lines = result["total"]["total_lines"].as_u64().unwrap();
     // 10 (rs) + 8 (py) + 10 (js) = 28
     assert_eq!(total_lines, 28);
 }
+
+#[test]
+fn test_test_code_split() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("tests")).unwrap();
+    std::fs::write(
+        dir.path().join("lib.rs"),
+        "fn f() {}\n\n#[cfg(test)]\nmod tests {\n    fn t() {}\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("tests").join("it.rs"),
+        "fn a() {}\nfn b() {}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("app_test.go"), "package app\n").unwrap();
+
+    let result = run_qcount_json(dir.path(), &[]);
+
+    assert_eq!(result["total"]["code_lines"].as_u64().unwrap(), 8);
+    assert_eq!(result["total"]["test_code_lines"].as_u64().unwrap(), 7);
+    assert_eq!(
+        result["total"]["production_code_lines"].as_u64().unwrap(),
+        1
+    );
+}
+
+#[test]
+fn test_report_meta_and_schema_version() {
+    let fixtures = fixtures_dir();
+    let result = run_qcount_json(&fixtures, &[]);
+
+    assert_eq!(result["schema_version"].as_u64().unwrap(), 1);
+    assert!(result["total"].get("language").is_none());
+    let meta = &result["meta"];
+    assert_eq!(meta["tool_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        meta["roots"][0].as_str().unwrap(),
+        fixtures.to_str().unwrap()
+    );
+    assert!(meta["timestamp"].as_str().unwrap().ends_with('Z'));
+    assert!(meta["threads"].as_u64().unwrap() >= 1);
+    assert_eq!(meta["skipped_files"].as_u64().unwrap(), 0);
+}
+
+#[test]
+fn test_published_schema_is_current() {
+    let generated: serde_json::Value =
+        serde_json::from_slice(&run_qcount(&["--json-schema"])).unwrap();
+
+    let published_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("schema")
+        .join("qcount-report.v1.schema.json");
+    let published: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(published_path).unwrap()).unwrap();
+
+    assert_eq!(
+        generated, published,
+        "schema/qcount-report.v1.schema.json is stale; regenerate with `qcount --json-schema`"
+    );
+}
+
+#[test]
+fn test_json_output_validates_against_schema() {
+    let schema: serde_json::Value =
+        serde_json::from_slice(&run_qcount(&["--json-schema"])).unwrap();
+
+    let result = run_qcount_json(&fixtures_dir(), &[]);
+
+    let validator = jsonschema::validator_for(&schema).expect("invalid schema");
+    let errors: Vec<String> = validator
+        .iter_errors(&result)
+        .map(|e| e.to_string())
+        .collect();
+    assert!(errors.is_empty(), "schema violations: {:?}", errors);
+}
+
+#[test]
+fn test_text_stats_in_json() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.py"), "x = 1\n# 123456789\n").unwrap();
+
+    let result = run_qcount_json(dir.path(), &["--line-width", "5"]);
+
+    let text = &result["by_language"][0]["text"];
+    assert_eq!(text["bytes"].as_u64().unwrap(), 18);
+    assert_eq!(text["chars"].as_u64().unwrap(), 18);
+    assert_eq!(text["max_line_length"].as_u64().unwrap(), 11);
+    assert_eq!(text["mean_line_length"].as_f64().unwrap(), 8.0);
+    assert_eq!(text["long_lines"].as_u64().unwrap(), 1);
+    assert_eq!(result["meta"]["long_line_width"].as_u64().unwrap(), 5);
+}
+
+#[test]
+fn test_estimate_in_json() {
+    let fixtures = fixtures_dir();
+    let result = run_qcount_json(&fixtures, &["--estimate", "--cocomo-mode", "semi-detached"]);
+
+    let estimate = &result["estimate"];
+    assert_eq!(estimate["mode"], "semi-detached");
+    assert_eq!(estimate["code_lines"].as_u64().unwrap(), 17);
+    assert!(estimate["cost"].as_f64().unwrap() > 0.0);
+
+    let without = run_qcount_json(&fixtures, &[]);
+    assert!(without.get("estimate").is_none());
+}
+
+#[test]
+fn test_multiple_roots_with_overlap() {
+    let dir = tempfile::tempdir().unwrap();
+    let a = dir.path().join("a");
+    let b = dir.path().join("b");
+    std::fs::create_dir_all(a.join("nested")).unwrap();
+    std::fs::create_dir(&b).unwrap();
+    std::fs::write(a.join("x.rs"), "fn x() {}\n").unwrap();
+    std::fs::write(a.join("nested").join("y.rs"), "fn y() {}\nfn z() {}\n").unwrap();
+    std::fs::write(b.join("z.py"), "z = 1\n").unwrap();
+
+    let nested = a.join("nested");
+    let result = run_qcount_json(&a, &[b.to_str().unwrap(), nested.to_str().unwrap()]);
+
+    assert_eq!(result["total"]["files"].as_u64().unwrap(), 3);
+    assert_eq!(result["total"]["code_lines"].as_u64().unwrap(), 4);
+    let by_root = result["by_root"].as_array().unwrap();
+    assert_eq!(by_root.len(), 3);
+    assert_eq!(by_root[0]["total"]["files"].as_u64().unwrap(), 2);
+    assert_eq!(by_root[1]["total"]["files"].as_u64().unwrap(), 1);
+    assert_eq!(by_root[2]["total"]["files"].as_u64().unwrap(), 0);
+}
+
+#[test]
+fn test_by_package() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let write = |rel: &str, content: &str| {
+        let path = root.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    };
+    write("Cargo.toml", "[workspace]\nmembers = [\"crates/core\"]\n");
+    write("crates/core/Cargo.toml", "[package]\nname = \"core\"\n");
+    write("crates/core/src/lib.rs", "fn a() {}\n");
+    write("web/package.json", r#"{"name": "web"}"#);
+    write("web/src/app.js", "let a = 1;\nlet b = 2;\n");
+    write("svc/go.mod", "module example.com/svc\n");
+    write("svc/main.go", "package main\n");
+    write("build.sh", "echo hi\n");
+
+    let result = run_qcount_json(root, &["--by-package"]);
+
+    let by_package = result["by_package"].as_array().unwrap();
+    let find = |name: &str| {
+        by_package
+            .iter()
+            .find(|p| p["package"]["name"] == name)
+            .unwrap_or_else(|| panic!("package {name} not found"))
+    };
+    let core = find("core");
+    assert_eq!(core["package"]["kind"], "cargo");
+    // lib.rs + Cargo.toml
+    assert_eq!(core["total"]["files"].as_u64().unwrap(), 2);
+    assert_eq!(find("web")["total"]["code_lines"].as_u64().unwrap(), 2);
+    assert_eq!(find("example.com/svc")["package"]["kind"], "go");
+
+    let workspace = by_package
+        .iter()
+        .find(|p| p["package"]["workspace"] == true)
+        .unwrap();
+    // build.sh + root Cargo.toml
+    assert_eq!(workspace["total"]["files"].as_u64().unwrap(), 2);
+}
+
+#[test]
+fn test_by_package_with_include() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let core = root.join("crates").join("core");
+    std::fs::create_dir_all(core.join("src")).unwrap();
+    std::fs::write(core.join("Cargo.toml"), "[package]\nname = \"core\"\n").unwrap();
+    std::fs::write(core.join("src").join("lib.rs"), "fn a() {}\n").unwrap();
+    std::fs::write(root.join("build.rs"), "fn main() {}\n").unwrap();
+
+    let result = run_qcount_json(root, &["--by-package", "--include", "*.rs"]);
+
+    assert_eq!(result["total"]["files"], 2);
+    let by_package = result["by_package"].as_array().unwrap();
+    assert_eq!(by_package.len(), 2);
+    assert_eq!(by_package[0]["package"]["name"], "core");
+    // lib.rs only; the manifest itself is not included
+    assert_eq!(by_package[0]["total"]["files"], 1);
+    assert!(by_package[1]["package"].is_null());
+}
+
+#[test]
+fn test_by_package_on_subdirectory() {
+    let dir = tempfile::tempdir().unwrap();
+    let web = dir.path().join("web");
+    std::fs::create_dir_all(web.join("src").join("lib")).unwrap();
+    std::fs::write(web.join("package.json"), r#"{"name": "@acme/web"}"#).unwrap();
+    std::fs::write(web.join("src").join("app.js"), "let a = 1;\n").unwrap();
+    std::fs::write(web.join("src").join("lib").join("b.js"), "let b = 2;\n").unwrap();
+
+    // The manifest sits above the counted root
+    let result = run_qcount_json(&web.join("src"), &["--by-package"]);
+
+    let by_package = result["by_package"].as_array().unwrap();
+    assert_eq!(by_package.len(), 1);
+    assert_eq!(by_package[0]["package"]["name"], "@acme/web");
+    assert_eq!(by_package[0]["total"]["files"], 2);
+}
+
+#[test]
+fn test_by_owner() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join(".github")).unwrap();
+    std::fs::create_dir_all(root.join("web")).unwrap();
+    std::fs::write(
+        root.join(".github").join("CODEOWNERS"),
+        "*.rs @acme/core\n/web/ @acme/web\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+    std::fs::write(root.join("web").join("app.js"), "let a = 1;\n").unwrap();
+    std::fs::write(root.join("gen.py"), "x = 1\n").unwrap();
+
+    let result = run_qcount_json(root, &["--by-owner"]);
+
+    let by_owner = result["by_owner"].as_array().unwrap();
+    assert_eq!(by_owner.len(), 2);
+    assert_eq!(by_owner[0]["owner"], "@acme/core");
+    assert_eq!(by_owner[0]["total"]["code_lines"].as_u64().unwrap(), 2);
+    assert_eq!(by_owner[1]["owner"], "@acme/web");
+    let unowned = result["unowned_files"].as_array().unwrap();
+    assert_eq!(unowned.len(), 1);
+    assert!(unowned[0].as_str().unwrap().ends_with("gen.py"));
+}
+
+#[test]
+fn test_by_owner_without_codeowners_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let bin = env!("CARGO_BIN_EXE_qcount");
+    let output = Command::new(bin)
+        .arg(dir.path())
+        .arg("--by-owner")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("CODEOWNERS"));
+}
+
+#[test]
+fn test_total_only() {
+    let fixtures = fixtures_dir();
+    let result = run_qcount_json(&fixtures, &["--total-only"]);
+
+    assert_eq!(result["meta"]["total_only"], true);
+    assert_eq!(result["total"]["total_lines"].as_u64().unwrap(), 28);
+    assert_eq!(result["total"]["blank_lines"].as_u64().unwrap(), 5);
+    assert_eq!(result["total"]["code_lines"].as_u64().unwrap(), 23);
+    assert_eq!(result["total"]["comment_lines"].as_u64().unwrap(), 0);
+}
+
+#[test]
+fn test_walker_options() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join(".github").join("workflows")).unwrap();
+    std::fs::create_dir_all(root.join("vendor")).unwrap();
+    std::fs::write(root.join(".github/workflows/ci.yml"), "on: push\n").unwrap();
+    std::fs::write(root.join("vendor/lib.js"), "let a = 1;\n").unwrap();
+    std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+
+    let files = |extra: &[&str]| {
+        run_qcount_json(root, extra)["total"]["files"]
+            .as_u64()
+            .unwrap()
+    };
+
+    assert_eq!(files(&[]), 2);
+    assert_eq!(files(&["--hidden"]), 3);
+    assert_eq!(files(&["--exclude", "vendor/"]), 1);
+    assert_eq!(
+        files(&["--include", "*.js", "--include", "*.yml", "--hidden"]),
+        2
+    );
+    assert_eq!(files(&["--max-depth", "1"]), 1);
+}
+
+#[test]
+fn test_missing_root_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let bin = env!("CARGO_BIN_EXE_qcount");
+    for args in [vec!["missing"], vec![".", "missing", "--no-config"]] {
+        let output = Command::new(bin)
+            .current_dir(dir.path())
+            .args(&args)
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("cannot read missing"));
+    }
+}
+
+#[test]
+fn test_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    std::fs::create_dir_all(root.join("vendor")).unwrap();
+    std::fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(root.join("vendor/lib.js"), "let a = 1;\n").unwrap();
+    std::fs::write(root.join("view.tpl"), "let b = 2;\n").unwrap();
+    // Discovered from an ancestor of the root
+    std::fs::write(
+        dir.path().join(".qcount.toml"),
+        "exclude = [\"vendor/\"]\nformat = \"json\"\nby_file = true\n\n\
+         [languages]\ntpl = \"javascript\"\n\n[thresholds]\nlong_line_width = 10\n",
+    )
+    .unwrap();
+
+    let result: serde_json::Value = serde_json::from_slice(&run_qcount(&[&root])).unwrap();
+
+    assert_eq!(result["total"]["files"], 2);
+    assert_eq!(result["meta"]["long_line_width"], 10);
+    assert_eq!(result["meta"]["excludes"][0], "vendor/");
+    assert!(
+        result["meta"]["config"]
+            .as_str()
+            .unwrap()
+            .ends_with(".qcount.toml")
+    );
+    let files = result["by_file"].as_array().unwrap();
+    assert_eq!(files.len(), 2);
+    assert!(files.iter().any(|f| f["language"] == "JavaScript"));
+
+    // Flags override the config; --no-config skips it
+    let output = run_qcount(&[
+        root.to_str().unwrap(),
+        "--line-width",
+        "80",
+        "--format",
+        "json",
+    ]);
+    let result: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(result["meta"]["long_line_width"], 80);
+
+    let result = run_qcount_json(&root, &["--no-by-file"]);
+    assert_eq!(result["total"]["files"], 2);
+    assert!(result["by_file"].as_array().unwrap().is_empty());
+
+    let result = run_qcount_json(&root, &["--no-config"]);
+    assert_eq!(result["total"]["files"], 2);
+    assert!(result["meta"]["config"].is_null());
+    assert!(result["by_file"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_config_globs_are_relative_to_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src").join("generated")).unwrap();
+    std::fs::write(root.join("src").join("main.rs"), "fn main() {}\n").unwrap();
+    std::fs::write(
+        root.join("src").join("generated").join("api.rs"),
+        "fn api() {}\n",
+    )
+    .unwrap();
+    std::fs::write(root.join(".qcount.toml"), "exclude = [\"src/generated\"]\n").unwrap();
+
+    // Walking a subdirectory still applies the repository-relative exclude
+    let result = run_qcount_json(&root.join("src"), &[]);
+    assert_eq!(result["total"]["files"], 1);
+}
+
+#[test]
+fn test_markers() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir(root.join("src")).unwrap();
+    std::fs::write(
+        root.join("src/lib.rs"),
+        "// TODO: split\nfn a() {} // TODO not a comment line\n/* FIXME\n   XXX later */\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("tool.py"), "# XXX hack\nx = 'TODO'\n").unwrap();
+
+    let result = run_qcount_json(
+        root,
+        &["--by-dir", "--markers", "TODO,FIXME,XXX", "--list-markers"],
+    );
+
+    assert_eq!(result["meta"]["markers"].as_array().unwrap().len(), 3);
+    let total = &result["total"]["markers"];
+    assert_eq!(total["TODO"], 1);
+    assert_eq!(total["FIXME"], 1);
+    assert_eq!(total["XXX"], 2);
+
+    let rust = result["by_language"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["language"] == "Rust")
+        .unwrap();
+    assert_eq!(rust["markers"]["XXX"], 1);
+
+    let src = result["by_directory"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["path"].as_str().unwrap().ends_with("src"))
+        .unwrap();
+    assert_eq!(src["by_language"]["Rust"]["markers"]["TODO"], 1);
+
+    let hits = result["marker_hits"].as_array().unwrap();
+    assert_eq!(hits.len(), 4);
+    assert_eq!(hits[0]["line"], 1);
+    assert_eq!(hits[0]["tag"], "TODO");
+    assert_eq!(hits[0]["text"], "// TODO: split");
+}
+
+#[test]
+fn test_licenses() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(
+        root.join("a.rs"),
+        "// SPDX-License-Identifier: MIT\nfn a() {}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("b.py"),
+        "#!/usr/bin/env python3\n# SPDX-License-Identifier: MIT\nx = 1\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("c.go"),
+        "// Licensed under the Apache License, Version 2.0\npackage c\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("d.js"), "let d = 1;\n").unwrap();
+
+    let result = run_qcount_json(root, &["--licenses"]);
+
+    let by_license = result["by_license"].as_array().unwrap();
+    assert_eq!(by_license.len(), 2);
+    assert_eq!(by_license[0]["license"], "MIT");
+    assert_eq!(by_license[0]["total"]["files"], 2);
+    assert_eq!(by_license[0]["by_language"].as_array().unwrap().len(), 2);
+    assert_eq!(by_license[1]["license"], "Apache-2.0");
+
+    let unlicensed = result["unlicensed_files"].as_array().unwrap();
+    assert_eq!(unlicensed.len(), 1);
+    assert!(unlicensed[0].as_str().unwrap().ends_with("d.js"));
+}
+
+#[test]
+fn test_hygiene() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::write(root.join("a.rs"), "fn a() {\n    x(); \n}\n").unwrap();
+    std::fs::write(root.join("b.rs"), "fn b() {\r\n\tx();\r\n}").unwrap();
+    std::fs::write(root.join("c.sh"), "echo a\r\necho b\n").unwrap();
+
+    let result = run_qcount_json(root, &["--hygiene"]);
+
+    assert_eq!(result["meta"]["hygiene"], true);
+    let total = &result["total"]["hygiene"];
+    assert_eq!(total["lf_files"], 1);
+    assert_eq!(total["crlf_files"], 1);
+    assert_eq!(total["mixed_line_ending_files"], 1);
+    assert_eq!(total["no_final_newline_files"], 1);
+    assert_eq!(total["trailing_whitespace_lines"], 1);
+    assert_eq!(total["tab_indented_lines"], 1);
+    assert_eq!(total["space_indented_lines"], 1);
+
+    let rust = result["by_language"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["language"] == "Rust")
+        .unwrap();
+    assert_eq!(rust["hygiene"]["crlf_files"], 1);
+
+    // Same numbers with the fast path
+    let fast = run_qcount_json(root, &["--hygiene", "--total-only"]);
+    assert_eq!(fast["total"]["hygiene"], *total);
+}
+
+#[test]
+fn test_churn_since() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    };
+    git(&["init", "-q"]);
+    std::fs::write(
+        root.join("lib.rs"),
+        "// Adds numbers\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("old.py"), "x = 1\n").unwrap();
+    let notebook = |run: u32, value: u32| {
+        format!(
+            r##"{{"cells": [{{"cell_type": "markdown", "metadata": {{}}, "source": ["Run {run}"]}},
+  {{"cell_type": "code", "execution_count": {run}, "metadata": {{}},
+  "outputs": [{{"output_type": "stream", "name": "stdout", "text": ["{value}\n"]}}],
+  "source": ["# show\n", "print({value})"]}}],
+ "metadata": {{"kernelspec": {{"language": "python"}}}}, "nbformat": 4, "nbformat_minor": 5}}"##
+        )
+    };
+    std::fs::write(root.join("nb.ipynb"), notebook(1, 1)).unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "base"]);
+
+    // Comment-only edit, one code line added, a file removed, a file added
+    std::fs::write(
+        root.join("lib.rs"),
+        "// Adds two numbers\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn one() {}\n",
+    )
+    .unwrap();
+    std::fs::remove_file(root.join("old.py")).unwrap();
+    std::fs::write(root.join("new.py"), "# helper\ny = 2\n").unwrap();
+    // Re-run with one code and one markdown line changed: outputs and
+    // execution counters do not count
+    std::fs::write(root.join("nb.ipynb"), notebook(2, 3)).unwrap();
+
+    let result = run_qcount_json(root, &["--since", "HEAD"]);
+
+    let churn = &result["churn"];
+    assert_eq!(churn["base"], "HEAD");
+    assert_eq!(churn["files"], 4);
+    let rust = churn["by_language"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["language"] == "Rust")
+        .unwrap();
+    assert_eq!(rust["churn"]["comment"]["modified"], 1);
+    assert_eq!(rust["churn"]["code"]["added"], 1);
+    assert_eq!(rust["churn"]["code"]["modified"], 0);
+    assert_eq!(rust["churn"]["blank"]["added"], 1);
+
+    let python = churn["by_language"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["language"] == "Python")
+        .unwrap();
+    assert_eq!(python["files"], 2);
+    assert_eq!(python["churn"]["code"]["added"], 1);
+    assert_eq!(python["churn"]["code"]["removed"], 1);
+    assert_eq!(python["churn"]["comment"]["added"], 1);
+
+    let jupyter = churn["by_language"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["language"] == "Jupyter")
+        .unwrap();
+    assert_eq!(
+        jupyter["churn"],
+        serde_json::json!({
+            "code": {"added": 0, "removed": 0, "modified": 1},
+            "comment": {"added": 0, "removed": 0, "modified": 0},
+            "blank": {"added": 0, "removed": 0, "modified": 0},
+            "doc": {"added": 0, "removed": 0, "modified": 1}
+        })
+    );
+
+    let bin = env!("CARGO_BIN_EXE_qcount");
+    let output = Command::new(bin)
+        .arg(root)
+        .args(["--json", "--since", "no-such-ref"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+#[cfg(unix)]
+fn test_churn_filters_like_the_walker() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["-c", "user.name=t", "-c", "user.email=t@example.com"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?}", args);
+    };
+    git(&["init", "-q"]);
+    std::fs::write(root.join("a*.py"), "a = 1\n").unwrap();
+    std::fs::write(root.join("ab.py"), "b = 1\n").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "base"]);
+
+    // `a*.py` is a file name, not a glob that also matches `ab.py`
+    std::fs::write(root.join("a*.py"), "a = 2\n").unwrap();
+    std::fs::write(root.join("ab.py"), "b = 2\n").unwrap();
+    std::fs::create_dir(root.join(".cache")).unwrap();
+    std::fs::write(root.join(".cache").join("c.py"), "c = 1\n").unwrap();
+    std::fs::write(root.join(".d.py"), "d = 1\n").unwrap();
+
+    let churn = |extra: &[&str]| {
+        let mut args = vec!["--since", "HEAD"];
+        args.extend(extra);
+        run_qcount_json(root, &args)["churn"].clone()
+    };
+    let result = churn(&[]);
+    assert_eq!(result["files"], 2);
+    assert_eq!(result["total"]["code"]["modified"], 2);
+    assert_eq!(result["total"]["code"]["added"], 0);
+    assert_eq!(churn(&["--hidden"])["files"], 4);
+}
+
+#[test]
+fn test_openmetrics_output() {
+    let output = run_qcount(&[
+        fixtures_dir().to_str().unwrap(),
+        "--format",
+        "openmetrics",
+        "--by-dir",
+    ]);
+    let text = String::from_utf8(output).unwrap();
+
+    assert!(text.contains("# TYPE qcount_lines gauge\n"));
+    assert!(text.contains("qcount_files{language=\"Rust\"} 1\n"));
+    assert!(
+        text.lines()
+            .any(|l| l.starts_with("qcount_lines{directory=\"") && l.contains("kind=\"code\""))
+    );
+    assert!(text.ends_with("# EOF\n"));
+}
+
+#[test]
+fn test_sqlite_export() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("history.db");
+    for label in ["first", "second"] {
+        run_qcount_json(
+            &fixtures_dir(),
+            &["--sqlite", db.to_str().unwrap(), "--label", label],
+        );
+    }
+
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let count = |sql: &str| -> i64 { conn.query_row(sql, [], |r| r.get(0)).unwrap() };
+    assert_eq!(count("SELECT COUNT(*) FROM runs"), 2);
+    assert_eq!(count("SELECT COUNT(*) FROM files WHERE run_id = 2"), 3);
+    assert_eq!(count("SELECT COUNT(*) FROM languages WHERE run_id = 1"), 3);
+    assert_eq!(
+        count("SELECT COUNT(*) FROM directories WHERE run_id = 1"),
+        3
+    );
+    assert_eq!(
+        count("SELECT SUM(code_lines) FROM files WHERE run_id = 1 AND language = 'Rust'"),
+        count("SELECT code_lines FROM languages WHERE run_id = 1 AND language = 'Rust'")
+    );
+    let label: String = conn
+        .query_row("SELECT label FROM runs WHERE id = 2", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(label, "second");
+}
+
+#[test]
+fn test_jupyter_notebook() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("analysis.ipynb"),
+        r##"{
+ "cells": [
+  {"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n", "Notes"]},
+  {"cell_type": "code", "execution_count": 1, "metadata": {},
+   "outputs": [{"output_type": "stream", "name": "stdout", "text": ["a\n", "b\n", "c\n"]}],
+   "source": ["# load\n", "import os\n", "print(os.name)"]}
+ ],
+ "metadata": {"language_info": {"name": "python"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##,
+    )
+    .unwrap();
+
+    let result = run_qcount_json(dir.path(), &[]);
+
+    let langs = result["by_language"].as_array().unwrap();
+    assert_eq!(langs.len(), 1);
+    let nb = &langs[0];
+    assert_eq!(nb["language"], "Jupyter");
+    assert_eq!(nb["files"], 1);
+    assert_eq!(nb["cells"], 2);
+    assert_eq!(nb["total_lines"], 5);
+    assert_eq!(nb["code_lines"], 2);
+    assert_eq!(nb["comment_lines"], 1);
+    assert_eq!(nb["doc_lines"], 2);
+
+    let markdown = run_qcount(&[dir.path().to_str().unwrap(), "--format", "markdown"]);
+    assert!(
+        String::from_utf8(markdown)
+            .unwrap()
+            .contains("Notebook cells: 2 (code and markdown); 2 documentation lines")
+    );
+}
+
+#[test]
+fn test_dedupe() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = "def f():\n    # doc\n    return 1\n";
+    std::fs::write(dir.path().join("a.py"), source).unwrap();
+    std::fs::write(dir.path().join("copy.py"), source).unwrap();
+    std::fs::write(dir.path().join("b.py"), "x = 1\n").unwrap();
+    #[cfg(unix)]
+    std::fs::hard_link(dir.path().join("b.py"), dir.path().join("link.py")).unwrap();
+
+    let run = |dedupe: bool| {
+        let extra: &[&str] = if dedupe { &["--dedupe"] } else { &[] };
+        run_qcount_json(dir.path(), extra)
+    };
+
+    let plain = run(false);
+    assert!(plain.get("duplicates").is_none());
+
+    let result = run(true);
+    assert_eq!(result["total"]["files"], 2);
+    assert_eq!(result["total"]["total_lines"], 4);
+    let dup = &result["duplicates"];
+    let links = if cfg!(unix) { 1 } else { 0 };
+    assert_eq!(dup["files"], 1 + links);
+    assert_eq!(dup["total_lines"], 3 + links);
+    assert_eq!(dup["code_lines"], 2 + links);
+    assert_eq!(dup["comment_lines"], 1);
+}
+
+#[test]
+fn test_symbols() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("lib.rs"),
+        "// fn commented_out() {}\npub mod walk;\npub struct Walker;\nimpl Walker {\n    pub fn new() -> Self {\n        Walker\n    }\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("app.py"),
+        "class App:\n    def run(self):\n        pass\n",
+    )
+    .unwrap();
+
+    let result = run_qcount_json(dir.path(), &["--symbols", "--by-file"]);
+
+    assert_eq!(result["meta"]["symbols"], true);
+    let total = &result["total"]["symbols"];
+    assert_eq!(total["functions"], 2);
+    assert_eq!(total["types"], 2);
+    assert_eq!(total["modules"], 1);
+    let rust = result["by_language"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["language"] == "Rust")
+        .unwrap();
+    assert_eq!(rust["symbols"]["functions"], 1);
+    let app = &result["by_file"][0];
+    assert!(app["path"].as_str().unwrap().ends_with("app.py"));
+    assert_eq!(app["symbols"]["types"], 1);
+}
+
+#[test]
+fn test_multiple_outputs() {
+    let out = tempfile::tempdir().unwrap();
+    let json_path = out.path().join("report.json");
+    let md_path = out.path().join("summary.md");
+
+    let output = run_qcount(&[
+        fixtures_dir().to_str().unwrap(),
+        "--output",
+        &format!("json={}", json_path.display()),
+        "--output",
+        &format!("markdown={}", md_path.display()),
+    ]);
+
+    // Table on stdout, the other formats in the files
+    let stdout = String::from_utf8(output).unwrap();
+    assert!(stdout.contains("Total: 3 files"));
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(json["total"]["files"], 3);
+    let markdown = std::fs::read_to_string(&md_path).unwrap();
+    assert!(markdown.starts_with("# qcount report\n"));
+    assert!(markdown.contains("| **Total** | 3 |"));
+}
+
+#[test]
+fn test_markdown_renders_breakdowns() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("web")).unwrap();
+    std::fs::write(root.join("CODEOWNERS"), "*.rs @acme/core\n").unwrap();
+    std::fs::write(root.join("lib.rs"), "fn a() {}\n").unwrap();
+    std::fs::write(root.join("web").join("app.js"), "let a = 1;\n").unwrap();
+
+    let output = run_qcount(&[
+        root.to_str().unwrap(),
+        "--format",
+        "markdown",
+        "--by-owner",
+        "--hygiene",
+        "--licenses",
+    ]);
+    let markdown = String::from_utf8(output).unwrap();
+
+    assert!(markdown.contains("\n## Hygiene\n"));
+    assert!(markdown.contains("\n## Owners\n\n### @acme/core\n"));
+    assert!(markdown.contains("\n## Unowned files (1)\n\n- `"));
+    assert!(markdown.contains("\n## Files without license header (2)\n"));
+}
+
+#[test]
+fn test_output_rejects_unknown_format() {
+    let bin = env!("CARGO_BIN_EXE_qcount");
+    let output = Command::new(bin)
+        .arg(fixtures_dir())
+        .args(["--output", "xml=report.xml"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_tui_without_terminal_fails_cleanly() {
+    let bin = env!("CARGO_BIN_EXE_qcount");
+    let output = Command::new(bin)
+        .arg(fixtures_dir())
+        .arg("--tui")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--tui needs an interactive terminal"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn test_baseline_ratchet() {
+    let dir = tempfile::tempdir().unwrap();
+    let legacy = dir.path().join("legacy");
+    std::fs::create_dir(&legacy).unwrap();
+    std::fs::write(legacy.join("old.py"), "a = 1\nb = 2\n").unwrap();
+    std::fs::write(dir.path().join("new.py"), "# doc\nc = 3\n").unwrap();
+    let baseline_path = dir.path().join("qcount-baseline.json");
+    std::fs::write(
+        &baseline_path,
+        r#"{"rules": [
+            {"path": "legacy", "metric": "code_lines", "ratchet": "max", "value": 5},
+            {"metric": "comment_density", "ratchet": "min"}
+        ]}"#,
+    )
+    .unwrap();
+
+    // Improved and unset rules pass and are tightened on request
+    let result = run_qcount_json(
+        dir.path(),
+        &[
+            "--baseline",
+            baseline_path.to_str().unwrap(),
+            "--update-baseline",
+        ],
+    );
+    assert_eq!(result["baseline"]["rules"][0]["status"], "improved");
+    assert_eq!(result["baseline"]["rules"][0]["current"], 2);
+    assert_eq!(result["baseline"]["rules"][1]["status"], "unset");
+    assert_eq!(result["baseline"]["updated"], true);
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&baseline_path).unwrap()).unwrap();
+    assert_eq!(saved["rules"][0]["value"], 2);
+    assert_eq!(saved["rules"][1]["value"], 0.25);
+
+    // Growing the deprecated directory now fails the run, after the report
+    std::fs::write(legacy.join("more.py"), "d = 4\n").unwrap();
+    let bin = env!("CARGO_BIN_EXE_qcount");
+    let output = Command::new(bin)
+        .arg(dir.path())
+        .arg("--json")
+        .arg("--baseline")
+        .arg(&baseline_path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["baseline"]["regressed"], 2);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 baseline rule(s) regressed"));
+}
+
+#[test]
+fn test_update_baseline_creates_missing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(root.join("src").join("lib.rs"), "// doc\nfn a() {}\n").unwrap();
+    let baseline_path = dir.path().join("qcount-baseline.json");
+    let baseline = baseline_path.to_str().unwrap();
+
+    let result = run_qcount_json(
+        &root,
+        &["--by-dir", "--baseline", baseline, "--update-baseline"],
+    );
+    assert_eq!(result["baseline"]["updated"], true);
+    let saved: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&baseline_path).unwrap()).unwrap();
+    let rules = saved["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), 3);
+    assert_eq!(rules[1]["language"], "Rust");
+    assert_eq!(rules[2]["path"], "src");
+    assert!(rules.iter().all(|r| r["value"] == 0.5));
+
+    // The seeded file is then enforced like a hand-written one
+    let result = run_qcount_json(&root, &["--baseline", baseline]);
+    assert_eq!(result["baseline"]["regressed"], 0);
+}
+
+fn cjk_fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("integration")
+        .join("fixtures_cjk")
+}
+
+/// (total, code, comment, blank) per language of the CJK fixtures.
+fn cjk_counts(extra: &[&str]) -> Vec<(String, u64, u64, u64, u64)> {
+    let result = run_qcount_json(&cjk_fixtures_dir(), extra);
+    let mut counts: Vec<_> = result["by_language"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| {
+            (
+                l["language"].as_str().unwrap().to_string(),
+                l["total_lines"].as_u64().unwrap(),
+                l["code_lines"].as_u64().unwrap(),
+                l["comment_lines"].as_u64().unwrap(),
+                l["blank_lines"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    counts.sort();
+    counts
+}
+
+#[test]
+fn test_cjk_unicode_blank_lines() {
+    // Ideographic spaces, NBSP and zero-width lines are blank; a BOM or an
+    // ideographic-space indent does not hide a comment
+    assert_eq!(
+        cjk_counts(&[]),
+        [
+            ("Python".to_string(), 6, 2, 2, 2),
+            ("Rust".to_string(), 15, 6, 6, 3),
+        ]
+    );
+}
+
+#[test]
+fn test_cjk_strict_ascii_blank() {
+    assert_eq!(
+        cjk_counts(&["--strict-ascii-blank"]),
+        [
+            ("Python".to_string(), 6, 3, 2, 1),
+            ("Rust".to_string(), 15, 8, 5, 2),
+        ]
+    );
+    let total_only = cjk_counts(&["--total-only"]);
+    assert_eq!(total_only[1], ("Rust".to_string(), 15, 12, 0, 3));
+    let strict = cjk_counts(&["--total-only", "--strict-ascii-blank"]);
+    assert_eq!(strict[1], ("Rust".to_string(), 15, 13, 0, 2));
+}
@@ -0,0 +1,6 @@
+# -*- coding: utf-8 -*-
+　
+def 挨拶(名前):
+　　# 全角スペースの後のコメント
+    return f"こんにちは、{名前}さん"
+⁠
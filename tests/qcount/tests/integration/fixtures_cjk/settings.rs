@@ -0,0 +1,15 @@
+﻿//! 設定ファイルの読み込み
+　
+/// 設定を表す構造体
+pub struct 設定 {
+    /// 名前（全角スペース区切り）
+    pub 名前: String,
+}
+  
+​
+　// 全角スペースで字下げしたコメント
+fn 読み込み() -> 設定 {
+    /* ブロック
+    　コメント */
+    設定 { 名前: "テスト".into() }
+}
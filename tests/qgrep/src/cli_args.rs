@@ -8,6 +8,28 @@ pub struct CliArgs {
     #[arg(short = 'F', long = "fixed-strings", action = ArgAction::SetTrue)]
     pub fixed_strings: bool,
 
+    #[arg(
+        short = 'i',
+        long = "ignore-case",
+        action = ArgAction::SetTrue,
+        overrides_with = "smart_case"
+    )]
+    pub ignore_case: bool,
+
+    #[arg(
+        short = 'S',
+        long = "smart-case",
+        action = ArgAction::SetTrue,
+        overrides_with = "ignore_case"
+    )]
+    pub smart_case: bool,
+
+    #[arg(short = 'w', long = "word-regexp", action = ArgAction::SetTrue)]
+    pub word_regexp: bool,
+
+    #[arg(short = 'x', long = "line-regexp", action = ArgAction::SetTrue)]
+    pub line_regexp: bool,
+
     #[arg(short = 'A', value_name = "N", allow_hyphen_values = true)]
     pub after_context: Option<i64>,
 
@@ -7,6 +7,7 @@ mod context_window;
 mod exit_code_policy;
 mod json_output_adapter;
 mod match_executor;
+mod match_options;
 mod match_record;
 mod search_issue;
 mod search_outcome;
@@ -112,6 +112,7 @@ mod tests {
 
     use crate::context_window::ContextWindow;
     use crate::match_executor::MatchExecutor;
+    use crate::match_options::MatchOptions;
     use crate::search_issue::SearchIssue;
     use crate::search_pattern::SearchPattern;
     use crate::search_request::SearchRequest;
@@ -208,7 +209,8 @@ mod tests {
         after_context: usize,
     ) -> SearchRequest {
         SearchRequest::new(
-            SearchPattern::from_cli("hit|match".to_string(), false).unwrap(),
+            SearchPattern::from_cli("hit|match".to_string(), false, MatchOptions::default())
+                .unwrap(),
             target,
             ContextWindow::new(before_context, after_context),
             false,
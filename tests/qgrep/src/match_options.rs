@@ -0,0 +1,36 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseMode {
+    #[default]
+    Sensitive,
+    Insensitive,
+    Smart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MatchOptions {
+    case_mode: CaseMode,
+    whole_word: bool,
+    whole_line: bool,
+}
+
+impl MatchOptions {
+    pub fn new(case_mode: CaseMode, whole_word: bool, whole_line: bool) -> Self {
+        Self {
+            case_mode,
+            whole_word,
+            whole_line,
+        }
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    pub fn whole_word(&self) -> bool {
+        self.whole_word
+    }
+
+    pub fn whole_line(&self) -> bool {
+        self.whole_line
+    }
+}
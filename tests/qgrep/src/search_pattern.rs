@@ -1,6 +1,8 @@
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
+use regex_syntax::ast::{self, Ast, ClassSetItem, Visitor};
 
 use crate::cli_error::CliError;
+use crate::match_options::{CaseMode, MatchOptions};
 
 #[derive(Debug, Clone)]
 pub enum SearchPattern {
@@ -9,15 +11,44 @@ pub enum SearchPattern {
 }
 
 impl SearchPattern {
-    pub fn from_cli(pattern: String, fixed_strings: bool) -> Result<Self, CliError> {
-        if fixed_strings {
+    pub fn from_cli(
+        pattern: String,
+        fixed_strings: bool,
+        match_options: MatchOptions,
+    ) -> Result<Self, CliError> {
+        let case_insensitive = match match_options.case_mode() {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            CaseMode::Smart if fixed_strings => !pattern.chars().any(char::is_uppercase),
+            CaseMode::Smart => !regex_has_uppercase_literal(&pattern),
+        };
+        let plain = !case_insensitive && !match_options.whole_word() && !match_options.whole_line();
+        if fixed_strings && plain {
             return Ok(Self::Fixed(pattern));
         }
 
-        let compiled = Regex::new(&pattern).map_err(|error| CliError::InvalidRegex {
-            pattern,
-            message: error.to_string(),
-        })?;
+        // Fixed strings go through the regex engine too, so that case folding
+        // and word and line boundaries follow the same Unicode rules.
+        let source = if fixed_strings {
+            regex::escape(&pattern)
+        } else {
+            pattern.clone()
+        };
+        let anchored = if match_options.whole_line() {
+            format!("^(?:{source})$")
+        } else if match_options.whole_word() {
+            format!(r"(?:^|\W)(?:{source})(?:\W|$)")
+        } else {
+            source
+        };
+
+        let compiled = RegexBuilder::new(&anchored)
+            .case_insensitive(case_insensitive)
+            .build()
+            .map_err(|error| CliError::InvalidRegex {
+                pattern,
+                message: error.to_string(),
+            })?;
 
         Ok(Self::Regex(compiled))
     }
@@ -30,15 +61,55 @@ impl SearchPattern {
     }
 }
 
+fn regex_has_uppercase_literal(pattern: &str) -> bool {
+    match ast::parse::Parser::new().parse(pattern) {
+        Ok(parsed) => ast::visit(&parsed, UppercaseLiteralFinder).unwrap_or(true),
+        // The regex builder reports the syntax error; this only picks the case mode
+        Err(_) => pattern.chars().any(char::is_uppercase),
+    }
+}
+
+struct UppercaseLiteralFinder;
+
+impl Visitor for UppercaseLiteralFinder {
+    type Output = bool;
+    type Err = ();
+
+    fn finish(self) -> Result<bool, ()> {
+        Ok(false)
+    }
+
+    fn visit_pre(&mut self, ast: &Ast) -> Result<(), ()> {
+        match ast {
+            Ast::Literal(literal) if literal.c.is_uppercase() => Err(()),
+            _ => Ok(()),
+        }
+    }
+
+    fn visit_class_set_item_pre(&mut self, item: &ClassSetItem) -> Result<(), ()> {
+        match item {
+            ClassSetItem::Literal(literal) if literal.c.is_uppercase() => Err(()),
+            ClassSetItem::Range(range)
+                if range.start.c.is_uppercase() || range.end.c.is_uppercase() =>
+            {
+                Err(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::SearchPattern;
     use crate::cli_error::CliError;
+    use crate::match_options::{CaseMode, MatchOptions};
 
     #[test]
     fn builds_regex_pattern_in_default_mode() {
-        let pattern = SearchPattern::from_cli("foo.*bar".to_string(), false)
-            .expect("regex mode should accept a valid regular expression");
+        let pattern =
+            SearchPattern::from_cli("foo.*bar".to_string(), false, MatchOptions::default())
+                .expect("regex mode should accept a valid regular expression");
 
         assert!(matches!(&pattern, SearchPattern::Regex(_)));
         assert!(pattern.is_match("foo123bar"));
@@ -47,8 +118,9 @@ mod tests {
 
     #[test]
     fn keeps_metacharacters_as_literals_in_fixed_mode() {
-        let pattern = SearchPattern::from_cli("foo.*bar".to_string(), true)
-            .expect("fixed-string mode should accept any input");
+        let pattern =
+            SearchPattern::from_cli("foo.*bar".to_string(), true, MatchOptions::default())
+                .expect("fixed-string mode should accept any input");
 
         assert!(matches!(&pattern, SearchPattern::Fixed(value) if value == "foo.*bar"));
         assert!(pattern.is_match("xxfoo.*baryy"));
@@ -57,7 +129,7 @@ mod tests {
 
     #[test]
     fn returns_error_for_invalid_regex() {
-        let error = SearchPattern::from_cli("(".to_string(), false)
+        let error = SearchPattern::from_cli("(".to_string(), false, MatchOptions::default())
             .expect_err("invalid regex must be treated as an input error");
 
         match error {
@@ -68,12 +140,129 @@ mod tests {
 
     #[test]
     fn checks_matching_by_search_mode() {
-        let regex_pattern = SearchPattern::from_cli("^foo".to_string(), false).unwrap();
+        let regex_pattern =
+            SearchPattern::from_cli("^foo".to_string(), false, MatchOptions::default()).unwrap();
         assert!(regex_pattern.is_match("foobar"));
         assert!(!regex_pattern.is_match("barfoo"));
 
-        let fixed_pattern = SearchPattern::from_cli("foo.*bar".to_string(), true).unwrap();
+        let fixed_pattern =
+            SearchPattern::from_cli("foo.*bar".to_string(), true, MatchOptions::default()).unwrap();
         assert!(fixed_pattern.is_match("xxfoo.*baryy"));
         assert!(!fixed_pattern.is_match("foobar"));
     }
+
+    fn options(case_mode: CaseMode, whole_word: bool, whole_line: bool) -> MatchOptions {
+        MatchOptions::new(case_mode, whole_word, whole_line)
+    }
+
+    #[test]
+    fn folds_unicode_case_for_fixed_strings() {
+        let pattern = SearchPattern::from_cli(
+            "ÄRGER.*".to_string(),
+            true,
+            options(CaseMode::Insensitive, false, false),
+        )
+        .unwrap();
+
+        assert!(pattern.is_match("kein ärger.* heute"));
+        assert!(!pattern.is_match("kein ärger heute"));
+    }
+
+    #[test]
+    fn ignores_case_for_regex_patterns() {
+        let pattern = SearchPattern::from_cli(
+            "foo\\d".to_string(),
+            false,
+            options(CaseMode::Insensitive, false, false),
+        )
+        .unwrap();
+
+        assert!(pattern.is_match("FOO1"));
+    }
+
+    #[test]
+    fn smart_case_is_insensitive_only_for_lowercase_patterns() {
+        let lowercase = SearchPattern::from_cli(
+            "foo".to_string(),
+            true,
+            options(CaseMode::Smart, false, false),
+        )
+        .unwrap();
+        assert!(lowercase.is_match("FOO"));
+
+        let mixed = SearchPattern::from_cli(
+            "Foo".to_string(),
+            true,
+            options(CaseMode::Smart, false, false),
+        )
+        .unwrap();
+        assert!(mixed.is_match("Foo"));
+        assert!(!mixed.is_match("foo"));
+    }
+
+    #[test]
+    fn smart_case_ignores_uppercase_escapes_in_regex() {
+        let escapes = SearchPattern::from_cli(
+            "foo\\S+\\W".to_string(),
+            false,
+            options(CaseMode::Smart, false, false),
+        )
+        .unwrap();
+        assert!(escapes.is_match("FOObar!"));
+
+        let class = SearchPattern::from_cli(
+            "[A-Z]oo".to_string(),
+            false,
+            options(CaseMode::Smart, false, false),
+        )
+        .unwrap();
+        assert!(class.is_match("Foo"));
+        assert!(!class.is_match("foo"));
+    }
+
+    #[test]
+    fn matches_whole_words_only() {
+        let fixed = SearchPattern::from_cli(
+            "a.b".to_string(),
+            true,
+            options(CaseMode::Sensitive, true, false),
+        )
+        .unwrap();
+        assert!(fixed.is_match("x a.b y"));
+        assert!(fixed.is_match("a.b"));
+        assert!(!fixed.is_match("xa.b"));
+        assert!(!fixed.is_match("axb"));
+
+        let regex = SearchPattern::from_cli(
+            "fo+|bar".to_string(),
+            false,
+            options(CaseMode::Sensitive, true, false),
+        )
+        .unwrap();
+        assert!(regex.is_match("(foo)"));
+        assert!(regex.is_match("bar baz"));
+        assert!(!regex.is_match("food"));
+        assert!(!regex.is_match("rebar"));
+    }
+
+    #[test]
+    fn matches_whole_lines_only() {
+        let regex = SearchPattern::from_cli(
+            "foo|bar".to_string(),
+            false,
+            options(CaseMode::Insensitive, true, true),
+        )
+        .unwrap();
+        assert!(regex.is_match("BAR"));
+        assert!(!regex.is_match("foo bar"));
+
+        let fixed = SearchPattern::from_cli(
+            "a+b".to_string(),
+            true,
+            options(CaseMode::Sensitive, false, true),
+        )
+        .unwrap();
+        assert!(fixed.is_match("a+b"));
+        assert!(!fixed.is_match(" a+b"));
+    }
 }
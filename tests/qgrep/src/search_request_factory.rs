@@ -1,6 +1,7 @@
 use crate::cli_args::CliArgs;
 use crate::cli_error::CliError;
 use crate::context_window::ContextWindow;
+use crate::match_options::{CaseMode, MatchOptions};
 use crate::search_pattern::SearchPattern;
 use crate::search_request::SearchRequest;
 
@@ -11,7 +12,13 @@ impl SearchRequestFactory {
         let before_context = resolve_before_context(&cli_args)?;
         let after_context = resolve_after_context(&cli_args)?;
 
-        let search_pattern = SearchPattern::from_cli(cli_args.pattern, cli_args.fixed_strings)?;
+        let match_options = MatchOptions::new(
+            resolve_case_mode(&cli_args),
+            cli_args.word_regexp,
+            cli_args.line_regexp,
+        );
+        let search_pattern =
+            SearchPattern::from_cli(cli_args.pattern, cli_args.fixed_strings, match_options)?;
         let context_window = ContextWindow::new(before_context, after_context);
 
         Ok(SearchRequest::new(
@@ -27,6 +34,16 @@ impl SearchRequestFactory {
     }
 }
 
+fn resolve_case_mode(cli_args: &CliArgs) -> CaseMode {
+    if cli_args.ignore_case {
+        CaseMode::Insensitive
+    } else if cli_args.smart_case {
+        CaseMode::Smart
+    } else {
+        CaseMode::Sensitive
+    }
+}
+
 fn resolve_before_context(cli_args: &CliArgs) -> Result<usize, CliError> {
     match cli_args.before_context {
         Some(value) => validate_context_value("-B", value),
@@ -90,6 +107,27 @@ mod tests {
         assert!(!search_request.pattern().is_match("abcXYZdef"));
     }
 
+    #[test]
+    fn builds_case_insensitive_fixed_string_request() {
+        let cli_args = CliArgs::try_parse_from(["qgrep", "-F", "-i", "ÄRGER.*", "src"]).unwrap();
+        let search_request = SearchRequestFactory::build(cli_args).unwrap();
+
+        assert!(matches!(search_request.pattern(), SearchPattern::Regex(_)));
+        assert!(search_request.pattern().is_match("kein ärger.*"));
+        assert!(!search_request.pattern().is_match("kein ärger"));
+    }
+
+    #[test]
+    fn lets_the_last_case_option_win() {
+        let cli_args = CliArgs::try_parse_from(["qgrep", "-i", "-S", "Needle", "src"]).unwrap();
+        let search_request = SearchRequestFactory::build(cli_args).unwrap();
+        assert!(!search_request.pattern().is_match("needle"));
+
+        let cli_args = CliArgs::try_parse_from(["qgrep", "-S", "-i", "Needle", "src"]).unwrap();
+        let search_request = SearchRequestFactory::build(cli_args).unwrap();
+        assert!(search_request.pattern().is_match("needle"));
+    }
+
     #[test]
     fn applies_c_option_to_both_context_sides() {
         let cli_args = CliArgs::try_parse_from(["qgrep", "-C", "3", "needle", "src"]).unwrap();
@@ -131,6 +131,7 @@ mod tests {
     use tempfile::tempdir;
 
     use crate::context_window::ContextWindow;
+    use crate::match_options::MatchOptions;
     use crate::search_pattern::SearchPattern;
     use crate::search_request::SearchRequest;
     use crate::target_filter_policy::TargetFilterPolicy;
@@ -261,7 +262,7 @@ mod tests {
         extension_excludes: Vec<&str>,
     ) -> SearchRequest {
         SearchRequest::new(
-            SearchPattern::from_cli("needle".to_string(), true).unwrap(),
+            SearchPattern::from_cli("needle".to_string(), true, MatchOptions::default()).unwrap(),
             target,
             ContextWindow::new(0, 0),
             false,
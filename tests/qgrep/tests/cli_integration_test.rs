@@ -171,6 +171,57 @@ fn exits_with_code_2_when_match_and_runtime_issue_coexist() {
         .stderr(predicate::str::contains("target decode failed"));
 }
 
+#[test]
+fn folds_case_for_fixed_strings_with_ignore_case() {
+    let temp_directory = tempdir().unwrap();
+    let target_file = temp_directory.path().join("target.txt");
+    fs::write(&target_file, "Straße ÄRGER\nstrasse\nkein ärger\n").unwrap();
+
+    let mut command = qgrep_command();
+    let assert = command
+        .args(["-F", "-i", "ärger"])
+        .arg(&target_file)
+        .assert()
+        .code(0);
+
+    let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let expected = format!(
+        "{path}:1:Straße ÄRGER\n{path}:3:kein ärger\n",
+        path = target_file.display()
+    );
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn applies_smart_case_word_and_line_options() {
+    let temp_directory = tempdir().unwrap();
+    let target_file = temp_directory.path().join("target.txt");
+    fs::write(&target_file, "Error\nerror: disk\nerrors\nno ERROR here\n").unwrap();
+
+    let mut command = qgrep_command();
+    let assert = command
+        .args(["-S", "-w", "error"])
+        .arg(&target_file)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let expected = format!(
+        "{path}:1:Error\n{path}:2:error: disk\n{path}:4:no ERROR here\n",
+        path = target_file.display()
+    );
+    assert_eq!(output, expected);
+
+    let mut command = qgrep_command();
+    let assert = command
+        .args(["-S", "-x", "Error"])
+        .arg(&target_file)
+        .assert()
+        .code(0);
+    let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert_eq!(output, format!("{}:1:Error\n", target_file.display()));
+}
+
 fn qgrep_command() -> Command {
     Command::new(assert_cmd::cargo::cargo_bin!("qgrep"))
 }